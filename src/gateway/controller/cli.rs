@@ -1,50 +1,413 @@
 extern crate clap;
+extern crate rpassword;
 
 use super::super::super::usecase;
-use super::super::super::{TaskRepo, UserRepo};
+use super::super::super::{
+    Clock, Error, LoginAttemptRepo, LoginPolicy, PasswordPolicy, Priority, SortDirection,
+    TagFilter, TaskQuery, TaskRepo, TaskSort, TaskSortKey, User, UserRepo,
+};
 use super::super::controller;
-use std::error;
+use super::super::controller::{http, rpc, shell, tui};
+use super::super::presenter;
+use super::super::presenter::{compact, json, table, template, text};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use rustyline::error::ReadlineError;
+use rustyline::Editor;
+use std::env;
+use std::fs;
+use std::io;
+use std::iter;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
 
 pub struct App<'a> {
     user_repo: &'a mut Box<dyn UserRepo>,
     task_repo: &'a mut Box<dyn TaskRepo>,
-    user_renderer: &'a Box<dyn controller::UserRenderer>,
-    task_renderer: &'a Box<dyn controller::TaskRenderer>,
+    login_attempt_repo: &'a mut Box<dyn LoginAttemptRepo>,
+    user_renderer: Box<dyn controller::UserRenderer>,
+    task_renderer: Box<dyn controller::TaskRenderer>,
     session_manager: &'a mut Box<dyn controller::SessionManager>,
+    clock: &'a dyn Clock,
+    password_policy: &'a PasswordPolicy,
+    login_policy: &'a LoginPolicy,
+    history_path: Option<&'a Path>,
+    session: String,
 }
 
 impl<'a> App<'a> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         user_repo: &'a mut Box<dyn UserRepo>,
         task_repo: &'a mut Box<dyn TaskRepo>,
-        user_renderer: &'a Box<dyn controller::UserRenderer>,
-        task_renderer: &'a Box<dyn controller::TaskRenderer>,
+        login_attempt_repo: &'a mut Box<dyn LoginAttemptRepo>,
         session_manager: &'a mut Box<dyn controller::SessionManager>,
+        clock: &'a dyn Clock,
+        password_policy: &'a PasswordPolicy,
+        login_policy: &'a LoginPolicy,
+        history_path: Option<&'a Path>,
     ) -> Self {
         Self {
             user_repo,
             task_repo,
-            user_renderer,
-            task_renderer,
+            login_attempt_repo,
+            user_renderer: Box::new(text::Text {}),
+            task_renderer: Box::new(text::Text {}),
             session_manager,
+            clock,
+            password_policy,
+            login_policy,
+            history_path,
+            session: controller::DEFAULT_SESSION.to_string(),
         }
     }
 
-    pub fn run(&mut self) -> Result<(), Box<dyn error::Error>> {
-        let args = self.app().get_matches();
+    pub fn run(&mut self) -> Result<(), Error> {
+        match self.app().get_matches_from_safe(env::args_os()) {
+            Ok(args) => self.run_matches(&args),
+            Err(err) => {
+                let words: Vec<String> = env::args_os()
+                    .map(|word| word.to_string_lossy().into_owned())
+                    .collect();
+                self.render_usage_error(&words, err)
+            }
+        }
+    }
+
+    fn run_matches(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
+        let result = self.run_command(args);
+        if let Err(err) = &result {
+            self.user_renderer.render_error(err);
+        }
+
+        result
+    }
+
+    fn run_command(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
+        self.select_renderers(args)?;
+        // A shell line picks a session only for itself, so lines without --session go back to the
+        // session the shell was started with.
+        let name = global_value(args, "session").unwrap_or(&self.session);
+        if name.trim().is_empty() {
+            return Err(Error::Validation(String::from(
+                "session name should not be empty",
+            )));
+        }
+        self.session_manager.select_session(name);
         match args.subcommand() {
             ("user", Some(args)) => self.run_user_command(args),
             ("task", Some(args)) => self.run_task_command(args),
-            _ => Err(From::from("unknown command")),
+            ("serve", Some(args)) => self.serve(args),
+            ("rpc", Some(_)) => self.serve_rpc(),
+            ("shell", Some(_)) => self.run_shell(args),
+            ("repair", Some(args)) => self.repair(args),
+            ("tui", Some(_)) => {
+                tui::Tui::new(self.task_repo, self.session_manager.as_ref(), self.clock).run()
+            }
+            _ => Err(Error::Validation(String::from("unknown command"))),
         }
     }
 
+    fn repair(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
+        let groups = usecase::FindDuplicateUsers::new(self.user_repo.as_ref())
+            .invoke()
+            .map_err(|err| err.context("failed to find duplicated users"))?;
+        if groups.is_empty() {
+            self.user_renderer
+                .render_message("No duplicated emails are found.");
+            return Ok(());
+        }
+
+        for group in &groups {
+            self.user_renderer.render_message(&format!(
+                "{} is shared by {} users; {} is the oldest and would be kept:",
+                group[0].email(),
+                group.len(),
+                group[0].id()
+            ));
+            for user in group {
+                self.user_renderer.render_user(user);
+            }
+        }
+        let emails: Vec<String> = args
+            .values_of("merge")
+            .map(|emails| emails.map(User::email_key).collect())
+            .unwrap_or_default();
+        if emails.is_empty() {
+            return Err(Error::Conflict(format!(
+                "found {} duplicated email(s); run `todo repair --merge <EMAIL>` to merge the users of an email into its oldest one and delete the others",
+                groups.len()
+            )));
+        }
+        // Every picked email is checked before anything is merged, so a typo merges nothing.
+        let mut picked = Vec::new();
+        for email in &emails {
+            match groups
+                .iter()
+                .find(|group| User::email_key(group[0].email()) == *email)
+            {
+                Some(group) => picked.push(group),
+                None => {
+                    return Err(Error::NotFound(format!(
+                        "no duplicated users share {}",
+                        email
+                    )))
+                }
+            }
+        }
+
+        for group in picked {
+            for user in &group[1..] {
+                self.session_manager
+                    .drop_session_records_of_user(user.id())?;
+            }
+            let kept = usecase::MergeUsers::new(self.user_repo, self.task_repo)
+                .invoke(group)
+                .map_err(|err| err.context("failed to merge duplicated users"))?;
+            self.user_renderer.render_message(&format!(
+                "Merged {} user(s) into {} and deleted them.",
+                group.len() - 1,
+                kept.id()
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn serve(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
+        let bind = args.value_of("bind").unwrap();
+        self.user_renderer
+            .render_message(&format!("Listening on http://{}", bind));
+
+        http::Server::new(
+            self.user_repo,
+            self.task_repo,
+            self.login_attempt_repo,
+            self.clock,
+            self.password_policy,
+            self.login_policy,
+        )
+        .serve(bind)
+    }
+
+    fn serve_rpc(&mut self) -> Result<(), Error> {
+        let stdin = io::stdin();
+        rpc::Server::new(self.task_repo, self.session_manager.as_ref(), self.clock)
+            .serve(stdin.lock(), io::stdout())
+    }
+
+    fn run_shell(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
+        if let Some(name) = global_value(args, "session") {
+            self.session = name.to_string();
+        }
+        let mut editor = Editor::<shell::Helper>::new();
+        editor.set_helper(Some(shell::Helper::new(self.command(&[]))));
+        if let Some(path) = self.history_path {
+            let _ = editor.load_history(path);
+        }
+
+        loop {
+            let (email, task_ids) = self.shell_state().unwrap_or_default();
+            let prompt = format!("{}> ", email);
+            if let Some(helper) = editor.helper_mut() {
+                helper.set_task_ids(task_ids);
+            }
+
+            let line = match editor.readline(&prompt) {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(err) => return Err(Error::Internal(err.to_string())),
+            };
+            let words = match shell::split_words(&line) {
+                Ok(words) if words.is_empty() => continue,
+                Ok(words) => words,
+                Err(err) => {
+                    self.user_renderer.render_error(&err);
+                    continue;
+                }
+            };
+            if !shell::has_secret(&words) {
+                editor.add_history_entry(line.as_str());
+            }
+            if words[0] == "exit" || words[0] == "quit" {
+                break;
+            }
+
+            match self
+                .app()
+                .get_matches_from_safe(iter::once(String::from("todo")).chain(words.clone()))
+            {
+                Ok(args) if args.subcommand_name() == Some("shell") => self
+                    .user_renderer
+                    .render_error(&Error::Validation(String::from("already in a shell"))),
+                Ok(args) => {
+                    let _ = self.run_matches(&args);
+                }
+                Err(err) => {
+                    self.select_renderers(args)?;
+                    let _ = self.render_usage_error(&words, err);
+                }
+            }
+        }
+
+        match self.history_path {
+            Some(path) => save_history(&mut editor, path),
+            None => Ok(()),
+        }
+    }
+
+    fn shell_state(&self) -> Result<(String, Vec<String>), Error> {
+        let user_id = match self.session_manager.pop_authenticated_user_id()? {
+            Some(user_id) => user_id,
+            None => return Ok((String::from("todo"), Vec::new())),
+        };
+        let user = usecase::GetUser::new(self.user_repo.as_ref()).invoke(&user_id)?;
+        let tasks = usecase::QueryTasks::new(self.task_repo.as_ref())
+            .invoke(&user_id, &TaskQuery::default())?;
+
+        Ok((
+            user.email().to_string(),
+            tasks
+                .into_iter()
+                .map(|task| task.id().to_string())
+                .collect(),
+        ))
+    }
+
+    fn render_usage_error(&mut self, words: &[String], err: clap::Error) -> Result<(), Error> {
+        if !err.use_stderr() {
+            println!("{}", err.message);
+            return Ok(());
+        }
+
+        if has_json_output(words) {
+            self.user_renderer = Box::new(json::Json {});
+        }
+        let err = Error::Validation(err.message);
+        self.user_renderer.render_error(&err);
+
+        Err(err)
+    }
+
+    fn select_renderers(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
+        let output = global_value(args, "output").unwrap_or("text");
+        if output == "json" {
+            self.user_renderer = Box::new(json::Json {});
+            self.task_renderer = Box::new(json::Json {});
+            return Ok(());
+        }
+
+        self.user_renderer = Box::new(text::Text {});
+        // Templates only shape task output, so a bad TODO_TEMPLATE must not break other commands.
+        let lists_tasks = args.subcommand_name() == Some("task");
+        let template = env::var("TODO_TEMPLATE")
+            .ok()
+            .filter(|t| lists_tasks && !t.is_empty());
+        self.task_renderer = match (
+            global_value(args, "template").filter(|_| lists_tasks),
+            global_value(args, "format"),
+            template.as_deref(),
+        ) {
+            (Some(template), _, _) | (None, None, Some(template)) => {
+                Box::new(template::Template::new(template)?)
+            }
+            (None, Some("table"), _) => Box::new(table::Table::new(presenter::terminal_width())),
+            (None, Some("compact"), _) => {
+                Box::new(compact::Compact::new(presenter::terminal_width()))
+            }
+            _ => Box::new(text::Text {}),
+        };
+
+        Ok(())
+    }
+
     fn app<'b, 'c>(&self) -> clap::App<'b, 'c> {
-        clap::App::new("todo").subcommands(vec![self.user_command(), self.task_command()])
+        clap::App::new("todo")
+            .arg(
+                clap::Arg::with_name("output")
+                    .long("output")
+                    .global(true)
+                    .takes_value(true)
+                    .possible_values(&["text", "json"]),
+            )
+            .arg(
+                clap::Arg::with_name("format")
+                    .long("format")
+                    .global(true)
+                    .takes_value(true)
+                    .possible_values(&["text", "table", "compact"]),
+            )
+            .arg(
+                clap::Arg::with_name("template")
+                    .long("template")
+                    .global(true)
+                    .takes_value(true)
+                    .conflicts_with("format"),
+            )
+            .arg(
+                clap::Arg::with_name("session")
+                    .long("session")
+                    .global(true)
+                    .takes_value(true),
+            )
+            .subcommands(self.subcommands(&[]))
+    }
+
+    fn command(&self, path: &[&str]) -> shell::Command {
+        let subcommands = self
+            .subcommands(path)
+            .iter()
+            .map(|sub| self.command(&[path, &[sub.get_name()]].concat()))
+            .collect();
+
+        shell::Command::new(path.last().unwrap_or(&"todo"), subcommands)
+    }
+
+    fn subcommands<'b, 'c>(&self, path: &[&str]) -> Vec<clap::App<'b, 'c>> {
+        match path {
+            [] => vec![
+                clap::SubCommand::with_name("user").subcommands(self.subcommands(&["user"])),
+                clap::SubCommand::with_name("task").subcommands(self.subcommands(&["task"])),
+                self.serve_command(),
+                clap::SubCommand::with_name("rpc"),
+                clap::SubCommand::with_name("shell"),
+                clap::SubCommand::with_name("tui"),
+                clap::SubCommand::with_name("repair")
+                    .about("Reports users sharing an email; merging them deletes all but the oldest account")
+                    .arg(
+                        clap::Arg::with_name("merge")
+                            .long("merge")
+                            .value_name("EMAIL")
+                            .takes_value(true)
+                            .multiple(true)
+                            .number_of_values(1)
+                            .help(
+                                "Moves the tasks of the users sharing EMAIL to the oldest of them and deletes the other accounts",
+                            ),
+                    ),
+            ],
+            ["user"] => self.user_commands(),
+            ["task"] => self.task_commands(),
+            ["task", "tag"] => vec![
+                self.task_tag_command("add"),
+                self.task_tag_command("remove"),
+            ],
+            _ => Vec::new(),
+        }
     }
 
-    fn user_command<'b, 'c>(&self) -> clap::App<'b, 'c> {
-        clap::SubCommand::with_name("user").subcommands(vec![
+    fn serve_command<'b, 'c>(&self) -> clap::App<'b, 'c> {
+        clap::SubCommand::with_name("serve").arg(
+            clap::Arg::with_name("bind")
+                .long("bind")
+                .takes_value(true)
+                .default_value("127.0.0.1:8080"),
+        )
+    }
+
+    fn user_commands<'b, 'c>(&self) -> Vec<clap::App<'b, 'c>> {
+        vec![
             clap::SubCommand::with_name("create")
                 .arg(
                     clap::Arg::with_name("email")
@@ -54,9 +417,13 @@ impl<'a> App<'a> {
                 )
                 .arg(
                     clap::Arg::with_name("password")
-                        .required(true)
                         .long("password")
                         .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("password-stdin")
+                        .long("password-stdin")
+                        .conflicts_with("password"),
                 ),
             clap::SubCommand::with_name("login")
                 .arg(
@@ -67,24 +434,152 @@ impl<'a> App<'a> {
                 )
                 .arg(
                     clap::Arg::with_name("password")
-                        .required(true)
                         .long("password")
                         .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("password-stdin")
+                        .long("password-stdin")
+                        .conflicts_with("password"),
                 ),
-            clap::SubCommand::with_name("logout"),
-            clap::SubCommand::with_name("delete"),
-        ])
-    }
-
-    fn task_command<'b, 'c>(&self) -> clap::App<'b, 'c> {
-        clap::SubCommand::with_name("task").subcommands(vec![
-            clap::SubCommand::with_name("get"),
-            clap::SubCommand::with_name("create").arg(
-                clap::Arg::with_name("name")
+            clap::SubCommand::with_name("password")
+                .arg(
+                    clap::Arg::with_name("password")
+                        .long("password")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("new-password")
+                        .long("new-password")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("password-stdin")
+                        .long("password-stdin")
+                        .conflicts_with("password")
+                        .help(
+                            "Reads the current password from the first line of stdin and, unless --new-password is given, the new one from the second",
+                        ),
+                ),
+            clap::SubCommand::with_name("email").arg(
+                clap::Arg::with_name("email")
                     .required(true)
-                    .long("name")
+                    .long("email")
                     .takes_value(true),
             ),
+            clap::SubCommand::with_name("sessions"),
+            clap::SubCommand::with_name("logout").arg(clap::Arg::with_name("all").long("all")),
+            clap::SubCommand::with_name("delete"),
+        ]
+    }
+
+    fn task_commands<'b, 'c>(&self) -> Vec<clap::App<'b, 'c>> {
+        vec![
+            clap::SubCommand::with_name("get")
+                .arg(
+                    clap::Arg::with_name("completed")
+                        .long("completed")
+                        .conflicts_with("not-completed"),
+                )
+                .arg(clap::Arg::with_name("not-completed").long("not-completed"))
+                .arg(clap::Arg::with_name("name").long("name").takes_value(true))
+                .arg(
+                    clap::Arg::with_name("tag")
+                        .long("tag")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1),
+                )
+                .arg(
+                    clap::Arg::with_name("any-tag")
+                        .long("any-tag")
+                        .requires("tag"),
+                )
+                .arg(
+                    clap::Arg::with_name("due-before")
+                        .long("due-before")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("due-after")
+                        .long("due-after")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("overdue")
+                        .long("overdue")
+                        .conflicts_with_all(&[
+                            "completed",
+                            "due-within",
+                            "due-before",
+                            "due-after",
+                        ]),
+                )
+                .arg(
+                    clap::Arg::with_name("due-within")
+                        .long("due-within")
+                        .takes_value(true)
+                        .value_name("duration")
+                        .conflicts_with_all(&["completed", "due-before", "due-after"]),
+                )
+                .arg(
+                    clap::Arg::with_name("sort")
+                        .long("sort")
+                        .takes_value(true)
+                        .possible_values(&TaskSortKey::NAMES),
+                )
+                .arg(
+                    clap::Arg::with_name("order")
+                        .long("order")
+                        .takes_value(true)
+                        .possible_values(&SortDirection::NAMES),
+                )
+                .arg(
+                    clap::Arg::with_name("limit")
+                        .long("limit")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("offset")
+                        .long("offset")
+                        .takes_value(true),
+                ),
+            clap::SubCommand::with_name("create")
+                .arg(
+                    clap::Arg::with_name("name")
+                        .required(true)
+                        .long("name")
+                        .takes_value(true),
+                )
+                .arg(clap::Arg::with_name("due").long("due").takes_value(true))
+                .arg(
+                    clap::Arg::with_name("priority")
+                        .long("priority")
+                        .takes_value(true)
+                        .possible_values(&Priority::NAMES),
+                ),
+            clap::SubCommand::with_name("edit")
+                .arg(
+                    clap::Arg::with_name("id")
+                        .required(true)
+                        .long("id")
+                        .takes_value(true),
+                )
+                .arg(clap::Arg::with_name("name").long("name").takes_value(true))
+                .arg(clap::Arg::with_name("reopen").long("reopen"))
+                .arg(
+                    clap::Arg::with_name("due")
+                        .long("due")
+                        .takes_value(true)
+                        .conflicts_with("no-due"),
+                )
+                .arg(clap::Arg::with_name("no-due").long("no-due"))
+                .arg(
+                    clap::Arg::with_name("priority")
+                        .long("priority")
+                        .takes_value(true)
+                        .possible_values(&Priority::NAMES),
+                ),
             clap::SubCommand::with_name("complete").arg(
                 clap::Arg::with_name("id")
                     .required(true)
@@ -97,32 +592,70 @@ impl<'a> App<'a> {
                     .long("id")
                     .takes_value(true),
             ),
-        ])
+            clap::SubCommand::with_name("move")
+                .arg(
+                    clap::Arg::with_name("id")
+                        .required(true)
+                        .long("id")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("before")
+                        .long("before")
+                        .takes_value(true),
+                )
+                .arg(clap::Arg::with_name("to-top").long("to-top"))
+                .arg(clap::Arg::with_name("to-bottom").long("to-bottom"))
+                .group(
+                    clap::ArgGroup::with_name("to")
+                        .args(&["before", "to-top", "to-bottom"])
+                        .required(true),
+                ),
+            clap::SubCommand::with_name("tag").subcommands(self.subcommands(&["task", "tag"])),
+        ]
+    }
+
+    fn task_tag_command<'b, 'c>(&self, name: &str) -> clap::App<'b, 'c> {
+        clap::SubCommand::with_name(name)
+            .arg(
+                clap::Arg::with_name("id")
+                    .required(true)
+                    .long("id")
+                    .takes_value(true),
+            )
+            .arg(
+                clap::Arg::with_name("tag")
+                    .required(true)
+                    .long("tag")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1),
+            )
     }
 }
 
 impl<'a> App<'a> {
-    fn run_user_command(&mut self, args: &clap::ArgMatches) -> Result<(), Box<dyn error::Error>> {
+    fn run_user_command(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
         match args.subcommand() {
             ("create", Some(args)) => self.create_user(args),
             ("login", Some(args)) => self.authenticate_user(args),
-            ("logout", Some(_)) => self.deauthenticate_user(),
+            ("password", Some(args)) => self.change_password(args),
+            ("email", Some(args)) => self.change_email(args),
+            ("sessions", Some(_)) => self.get_sessions(),
+            ("logout", Some(args)) => self.deauthenticate_user(args),
             ("delete", Some(_)) => self.delete_user(),
-            _ => Err(From::from("unknown command")),
+            _ => Err(Error::Validation(String::from("unknown command"))),
         }
     }
 
-    fn create_user(&mut self, args: &clap::ArgMatches) -> Result<(), Box<dyn error::Error>> {
-        let (email, password) = (
-            args.value_of("email").unwrap(),
-            args.value_of("password").unwrap(),
-        );
-        let user = usecase::CreateUser::new(self.user_repo)
-            .invoke(email, password)
-            .map_err(|err| format!("failed to create user: {}", err))?;
+    fn create_user(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
+        let email = args.value_of("email").unwrap();
+        let password = read_password(args, "password", "Password: ", true)?;
+        let user = usecase::CreateUser::new(self.user_repo, self.clock, self.password_policy)
+            .invoke(email, &password)
+            .map_err(|err| err.context("failed to create user"))?;
 
-        self.session_manager
-            .push_authenticated_user_id(&user.id())?;
+        self.session_manager.push_authenticated_user_id(user.id())?;
 
         self.user_renderer
             .render_message("User is successfully created.");
@@ -131,19 +664,21 @@ impl<'a> App<'a> {
         Ok(())
     }
 
-    fn authenticate_user(&mut self, args: &clap::ArgMatches) -> Result<(), Box<dyn error::Error>> {
-        let (email, password) = (
-            args.value_of("email").unwrap(),
-            args.value_of("password").unwrap(),
-        );
-        let user = usecase::AuthenticateUser::new(self.user_repo)
-            .invoke(email, password)
-            .map_err(|err| format!("failed to authenticate user: {}", err))?;
+    fn authenticate_user(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
+        let email = args.value_of("email").unwrap();
+        let password = read_password(args, "password", "Password: ", false)?;
+        let user = usecase::AuthenticateUser::new(
+            self.user_repo.as_ref(),
+            self.login_attempt_repo,
+            self.clock,
+            self.login_policy,
+        )
+        .invoke(email, &password)
+        .map_err(|err| err.context("failed to authenticate user"))?;
 
         match user {
             Some(user) => {
-                self.session_manager
-                    .push_authenticated_user_id(&user.id())?;
+                self.session_manager.push_authenticated_user_id(user.id())?;
 
                 self.user_renderer
                     .render_message("You are succefully logged in.");
@@ -152,16 +687,89 @@ impl<'a> App<'a> {
 
                 Ok(())
             }
+            None => Err(Error::Unauthorized(String::from("invalid credentials"))),
+        }
+    }
+
+    fn change_password(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
+        let user_id = match self.session_manager.pop_authenticated_user_id()? {
+            Some(user_id) => user_id,
             None => {
-                self.user_renderer.render_error("Invalid credentials.");
+                return Err(Error::Unauthorized(String::from(
+                    "authentication is required",
+                )))
+            }
+        };
+        let current = read_password(args, "password", "Current password: ", false)?;
+        let password = read_password(args, "new-password", "New password: ", true)?;
+        let user = usecase::ChangePassword::new(
+            self.user_repo,
+            self.login_attempt_repo,
+            self.clock,
+            self.password_policy,
+            self.login_policy,
+        )
+        .invoke(&user_id, &current, &password)
+        .map_err(|err| err.context("failed to change password"))?;
+        self.session_manager.drop_other_sessions_of_user(&user_id)?;
 
-                Ok(())
+        self.user_renderer
+            .render_message("Your password is successfully changed.");
+        self.user_renderer.render_user(&user);
+
+        Ok(())
+    }
+
+    fn change_email(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
+        let user_id = match self.session_manager.pop_authenticated_user_id()? {
+            Some(user_id) => user_id,
+            None => {
+                return Err(Error::Unauthorized(String::from(
+                    "authentication is required",
+                )))
             }
-        }
+        };
+        let email = args.value_of("email").unwrap();
+        let user = usecase::ChangeEmail::new(self.user_repo)
+            .invoke(&user_id, email)
+            .map_err(|err| err.context("failed to change email"))?;
+
+        self.user_renderer
+            .render_message("Your email is successfully changed.");
+        self.user_renderer.render_user(&user);
+
+        Ok(())
+    }
+
+    fn get_sessions(&mut self) -> Result<(), Error> {
+        let user_id = match self.session_manager.pop_authenticated_user_id()? {
+            Some(user_id) => user_id,
+            None => {
+                return Err(Error::Unauthorized(String::from(
+                    "authentication is required",
+                )))
+            }
+        };
+
+        let sessions = self.session_manager.find_sessions_of_user(&user_id)?;
+        self.user_renderer.render_sessions(&sessions);
+
+        Ok(())
     }
 
-    fn deauthenticate_user(&mut self) -> Result<(), Box<dyn error::Error>> {
-        self.session_manager.drop_authenticated_user_id()?;
+    fn deauthenticate_user(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
+        if args.is_present("all") {
+            match self.session_manager.pop_authenticated_user_id()? {
+                Some(user_id) => self.session_manager.drop_sessions_of_user(&user_id)?,
+                None => {
+                    return Err(Error::Unauthorized(String::from(
+                        "authentication is required",
+                    )))
+                }
+            }
+        } else {
+            self.session_manager.drop_authenticated_user_id()?;
+        }
         self.user_renderer
             .render_message("You are successfully logged out.");
         self.user_renderer.render_message("See you later!");
@@ -169,18 +777,18 @@ impl<'a> App<'a> {
         Ok(())
     }
 
-    fn delete_user(&mut self) -> Result<(), Box<dyn error::Error>> {
+    fn delete_user(&mut self) -> Result<(), Error> {
         let user_id = match self.session_manager.pop_authenticated_user_id()? {
             Some(user_id) => user_id,
             None => {
-                self.user_renderer
-                    .render_error("authentication is required.");
-                return Ok(());
+                return Err(Error::Unauthorized(String::from(
+                    "authentication is required",
+                )))
             }
         };
 
-        self.session_manager.drop_authenticated_user_id()?;
-        usecase::DeleteUser::new(&mut self.user_repo, &mut self.task_repo).invoke(&user_id)?;
+        self.session_manager.drop_sessions_of_user(&user_id)?;
+        usecase::DeleteUser::new(self.user_repo, self.task_repo).invoke(&user_id)?;
 
         self.user_renderer
             .render_message("Your data are completed deleted.");
@@ -191,47 +799,67 @@ impl<'a> App<'a> {
 }
 
 impl<'a> App<'a> {
-    fn run_task_command(&mut self, args: &clap::ArgMatches) -> Result<(), Box<dyn error::Error>> {
+    fn run_task_command(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
         match args.subcommand() {
-            ("get", Some(_)) => self.get_tasks(),
+            ("get", Some(args)) => self.get_tasks(args),
             ("create", Some(args)) => self.create_task(args),
+            ("edit", Some(args)) => self.edit_task(args),
             ("complete", Some(args)) => self.complete_task(args),
             ("delete", Some(args)) => self.delete_task(args),
-            _ => Err(From::from("unknown command")),
+            ("move", Some(args)) => self.move_task(args),
+            ("tag", Some(args)) => self.run_task_tag_command(args),
+            _ => Err(Error::Validation(String::from("unknown command"))),
         }
     }
 
-    fn get_tasks(&self) -> Result<(), Box<dyn error::Error>> {
+    fn run_task_tag_command(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
+        match args.subcommand() {
+            ("add", Some(args)) => self.tag_task(args, true),
+            ("remove", Some(args)) => self.tag_task(args, false),
+            _ => Err(Error::Validation(String::from("unknown command"))),
+        }
+    }
+
+    fn get_tasks(&self, args: &clap::ArgMatches) -> Result<(), Error> {
         let user_id = match self.session_manager.pop_authenticated_user_id()? {
             Some(user_id) => user_id,
             None => {
-                self.task_renderer
-                    .render_error("authentication is required.");
-                return Ok(());
+                return Err(Error::Unauthorized(String::from(
+                    "authentication is required",
+                )))
             }
         };
-        let tasks = usecase::GetTasks::new(self.task_repo)
-            .invoke(&user_id)
-            .map_err(|err| format!("failed to get tasks: {}", err))?;
+        let query = task_query(args, self.clock.now())?;
+        let tasks = usecase::QueryTasks::new(self.task_repo.as_ref())
+            .invoke(&user_id, &query)
+            .map_err(|err| err.context("failed to get tasks"))?;
 
         self.task_renderer.render_tasks(&tasks);
 
         Ok(())
     }
 
-    fn create_task(&mut self, args: &clap::ArgMatches) -> Result<(), Box<dyn error::Error>> {
+    fn create_task(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
         let user_id = match self.session_manager.pop_authenticated_user_id()? {
             Some(user_id) => user_id,
             None => {
-                self.task_renderer
-                    .render_error("authentication is required.");
-                return Ok(());
+                return Err(Error::Unauthorized(String::from(
+                    "authentication is required",
+                )))
             }
         };
         let name = args.value_of("name").unwrap();
-        let task = usecase::CreateTask::new(self.task_repo)
-            .invoke(&user_id, name)
-            .map_err(|err| format!("failed to create task: {}", err))?;
+        let due = match args.value_of("due") {
+            Some(due) => Some(parse_due(due)?),
+            None => None,
+        };
+        let priority = match args.value_of("priority") {
+            Some(priority) => Priority::new(priority)?,
+            None => Priority::None,
+        };
+        let task = usecase::CreateTask::new(self.task_repo, self.clock)
+            .invoke(&user_id, name, due, priority)
+            .map_err(|err| err.context("failed to create task"))?;
 
         self.task_renderer
             .render_message("Task is successfully created.");
@@ -240,17 +868,110 @@ impl<'a> App<'a> {
         Ok(())
     }
 
-    fn complete_task(&mut self, args: &clap::ArgMatches) -> Result<(), Box<dyn error::Error>> {
+    fn edit_task(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
+        let user_id = match self.session_manager.pop_authenticated_user_id()? {
+            Some(user_id) => user_id,
+            None => {
+                return Err(Error::Unauthorized(String::from(
+                    "authentication is required",
+                )))
+            }
+        };
+        let id = args.value_of("id").unwrap();
+        let mut update = usecase::TaskUpdate {
+            name: args.value_of("name").map(String::from),
+            ..Default::default()
+        };
+        if args.is_present("reopen") {
+            update.completed = Some(false);
+        }
+        if let Some(due) = args.value_of("due") {
+            update.due = Some(Some(parse_due(due)?));
+        }
+        if args.is_present("no-due") {
+            update.due = Some(None);
+        }
+        if let Some(priority) = args.value_of("priority") {
+            update.priority = Some(Priority::new(priority)?);
+        }
+        let task = usecase::UpdateTask::new(self.task_repo, self.clock)
+            .invoke(id, &user_id, &update)
+            .map_err(|err| err.context("failed to edit task"))?;
+
+        self.task_renderer
+            .render_message("The task is successfully edited.");
+        self.task_renderer.render_task(&task);
+
+        Ok(())
+    }
+
+    fn tag_task(&mut self, args: &clap::ArgMatches, add: bool) -> Result<(), Error> {
         let user_id = match self.session_manager.pop_authenticated_user_id()? {
             Some(user_id) => user_id,
             None => {
-                self.task_renderer
-                    .render_error("authentication is required.");
-                return Ok(());
+                return Err(Error::Unauthorized(String::from(
+                    "authentication is required",
+                )))
             }
         };
         let id = args.value_of("id").unwrap();
-        let task = usecase::CompleteTask::new(&mut self.task_repo).invoke(id, &user_id)?;
+        let tags: Vec<String> = args.values_of("tag").unwrap().map(String::from).collect();
+        let mut update = usecase::TaskUpdate::default();
+        if add {
+            update.add_tags = tags;
+        } else {
+            update.remove_tags = tags;
+        }
+        let task = usecase::UpdateTask::new(self.task_repo, self.clock)
+            .invoke(id, &user_id, &update)
+            .map_err(|err| err.context("failed to tag task"))?;
+
+        self.task_renderer
+            .render_message("The task is successfully tagged.");
+        self.task_renderer.render_task(&task);
+
+        Ok(())
+    }
+
+    fn move_task(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
+        let user_id = match self.session_manager.pop_authenticated_user_id()? {
+            Some(user_id) => user_id,
+            None => {
+                return Err(Error::Unauthorized(String::from(
+                    "authentication is required",
+                )))
+            }
+        };
+        let id = args.value_of("id").unwrap();
+        let to = if args.is_present("to-top") {
+            usecase::MoveTo::Top
+        } else if args.is_present("to-bottom") {
+            usecase::MoveTo::Bottom
+        } else {
+            usecase::MoveTo::Before(args.value_of("before").unwrap().to_string())
+        };
+        let task = usecase::MoveTask::new(self.task_repo, self.clock)
+            .invoke(id, &user_id, &to)
+            .map_err(|err| err.context("failed to move task"))?;
+
+        self.task_renderer
+            .render_message("The task is successfully moved.");
+        self.task_renderer.render_task(&task);
+
+        Ok(())
+    }
+
+    fn complete_task(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
+        let user_id = match self.session_manager.pop_authenticated_user_id()? {
+            Some(user_id) => user_id,
+            None => {
+                return Err(Error::Unauthorized(String::from(
+                    "authentication is required",
+                )))
+            }
+        };
+        let id = args.value_of("id").unwrap();
+        let task = usecase::CompleteTask::new(self.task_repo, self.clock).invoke(id, &user_id)?;
 
         self.task_renderer.render_message("The task is completed.");
         self.task_renderer.render_task(&task);
@@ -258,17 +979,17 @@ impl<'a> App<'a> {
         Ok(())
     }
 
-    fn delete_task(&mut self, args: &clap::ArgMatches) -> Result<(), Box<dyn error::Error>> {
+    fn delete_task(&mut self, args: &clap::ArgMatches) -> Result<(), Error> {
         let user_id = match self.session_manager.pop_authenticated_user_id()? {
             Some(user_id) => user_id,
             None => {
-                self.task_renderer
-                    .render_error("authentication is required.");
-                return Ok(());
+                return Err(Error::Unauthorized(String::from(
+                    "authentication is required",
+                )))
             }
         };
         let id = args.value_of("id").unwrap();
-        usecase::DeleteTask::new(&mut self.task_repo).invoke(id, &user_id)?;
+        usecase::DeleteTask::new(self.task_repo).invoke(id, &user_id)?;
 
         self.task_renderer
             .render_message("The task is successfully deleted.");
@@ -276,3 +997,167 @@ impl<'a> App<'a> {
         Ok(())
     }
 }
+
+fn read_password(
+    args: &clap::ArgMatches,
+    name: &str,
+    prompt: &str,
+    confirm: bool,
+) -> Result<String, Error> {
+    if let Some(password) = args.value_of(name) {
+        return Ok(password.to_string());
+    }
+
+    let failed = |err: io::Error| Error::Internal(format!("failed to read password: {}", err));
+    if args.is_present("password-stdin") {
+        let mut password = String::new();
+        io::stdin().read_line(&mut password).map_err(failed)?;
+        return Ok(password.trim_end_matches(&['\r', '\n'][..]).to_string());
+    }
+
+    let password = rpassword::read_password_from_tty(Some(prompt)).map_err(failed)?;
+    if confirm {
+        let confirmation =
+            rpassword::read_password_from_tty(Some("Confirm password: ")).map_err(failed)?;
+        if confirmation != password {
+            return Err(Error::Validation(String::from("passwords do not match")));
+        }
+    }
+
+    Ok(password)
+}
+
+fn global_value<'b>(args: &'b clap::ArgMatches, name: &str) -> Option<&'b str> {
+    let mut value = args.value_of(name);
+    let mut args = args;
+    while let (_, Some(sub)) = args.subcommand() {
+        value = sub.value_of(name).or(value);
+        args = sub;
+    }
+
+    value
+}
+
+// Arguments that clap rejected have no matches to read --output from.
+fn has_json_output(words: &[String]) -> bool {
+    words.iter().enumerate().any(|(i, word)| {
+        word == "--output=json"
+            || (word == "--output" && words.get(i + 1).map(String::as_str) == Some("json"))
+    })
+}
+
+fn save_history(editor: &mut Editor<shell::Helper>, path: &Path) -> Result<(), Error> {
+    editor
+        .save_history(path)
+        .map_err(|err| Error::Storage(err.to_string()))?;
+    #[cfg(unix)]
+    if path.exists() {
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok(())
+}
+
+fn task_query(args: &clap::ArgMatches, now: DateTime<Utc>) -> Result<TaskQuery, Error> {
+    let mut query = TaskQuery::default();
+    if args.is_present("completed") {
+        query.completed = Some(true);
+    }
+    if args.is_present("not-completed") {
+        query.completed = Some(false);
+    }
+    query.name_contains = args.value_of("name").map(String::from);
+    if let Some(tags) = args.values_of("tag") {
+        let tags: Vec<&str> = tags.collect();
+        query.tags = Some(if args.is_present("any-tag") {
+            TagFilter::any(&tags)?
+        } else {
+            TagFilter::all(&tags)?
+        });
+    }
+    if let Some(before) = args.value_of("due-before") {
+        query.due_before = Some(parse_due(before)?);
+    }
+    if let Some(after) = args.value_of("due-after") {
+        query.due_after = Some(parse_due(after)?);
+    }
+    if args.is_present("overdue") {
+        usecase::DueFilter::Overdue.apply(&mut query, now)?;
+    }
+    if let Some(within) = args.value_of("due-within") {
+        usecase::DueFilter::Within(parse_duration(within)?).apply(&mut query, now)?;
+    }
+    if let Some(key) = args.value_of("sort") {
+        query.sort = TaskSort::new(TaskSortKey::new(key)?);
+    }
+    if let Some(direction) = args.value_of("order") {
+        query.sort.direction = SortDirection::new(direction)?;
+    }
+    if let Some(limit) = args.value_of("limit") {
+        query.limit = Some(
+            limit
+                .parse()
+                .map_err(|_| Error::Validation(format!("invalid limit: {}", limit)))?,
+        );
+    }
+    if let Some(offset) = args.value_of("offset") {
+        query.offset = offset
+            .parse()
+            .map_err(|_| Error::Validation(format!("invalid offset: {}", offset)))?;
+    }
+
+    Ok(query)
+}
+
+fn parse_due(due: &str) -> Result<DateTime<Utc>, Error> {
+    if let Ok(due) = DateTime::parse_from_rfc3339(due) {
+        return Ok(due.with_timezone(&Utc));
+    }
+
+    let due = match NaiveDateTime::parse_from_str(due, "%Y-%m-%d %H:%M") {
+        Ok(due) => due,
+        Err(_) => NaiveDate::parse_from_str(due, "%Y-%m-%d")
+            .ok()
+            .and_then(|due| due.and_hms_opt(23, 59, 59))
+            .ok_or_else(|| {
+                Error::Validation(format!(
+                    "invalid due: {}: expected YYYY-MM-DD, \"YYYY-MM-DD HH:MM\" or RFC 3339",
+                    due
+                ))
+            })?,
+    };
+    match Local.from_local_datetime(&due).earliest() {
+        Some(due) => Ok(due.with_timezone(&Utc)),
+        None => Err(Error::Validation(format!(
+            "invalid due: {}: no such local time",
+            due
+        ))),
+    }
+}
+
+pub fn parse_duration(duration: &str) -> Result<Duration, Error> {
+    let invalid = || {
+        Error::Validation(format!(
+            "invalid duration: {}: expected such as 30m, 12h, 3d or 2w",
+            duration
+        ))
+    };
+
+    let unit_at = duration.len() - duration.chars().last().map_or(0, |c| c.len_utf8());
+    let n: i64 = duration[..unit_at].parse().map_err(|_| invalid())?;
+    if n <= 0 {
+        return Err(Error::Validation(format!(
+            "invalid duration: {}: must be positive",
+            duration
+        )));
+    }
+    let parsed = match &duration[unit_at..] {
+        "m" => Duration::try_minutes(n),
+        "h" => Duration::try_hours(n),
+        "d" => Duration::try_days(n),
+        "w" => Duration::try_weeks(n),
+        _ => return Err(invalid()),
+    };
+
+    parsed.ok_or_else(|| Error::Validation(format!("invalid duration: {}: out of range", duration)))
+}
@@ -0,0 +1,319 @@
+extern crate rand;
+extern crate serde_json;
+extern crate tiny_http;
+
+use super::super::super::usecase;
+use super::super::super::{
+    Clock, Error, LoginAttemptRepo, LoginPolicy, PasswordPolicy, TaskQuery, TaskRepo, UserRepo,
+};
+use super::super::presenter::json::Json;
+use super::params;
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::Cursor;
+use tiny_http::{Header, Method, Request, Response};
+
+pub struct Server<'a> {
+    user_repo: &'a mut Box<dyn UserRepo>,
+    task_repo: &'a mut Box<dyn TaskRepo>,
+    login_attempt_repo: &'a mut Box<dyn LoginAttemptRepo>,
+    clock: &'a dyn Clock,
+    password_policy: &'a PasswordPolicy,
+    login_policy: &'a LoginPolicy,
+    tokens: HashMap<String, String>,
+}
+
+impl<'a> Server<'a> {
+    pub fn new(
+        user_repo: &'a mut Box<dyn UserRepo>,
+        task_repo: &'a mut Box<dyn TaskRepo>,
+        login_attempt_repo: &'a mut Box<dyn LoginAttemptRepo>,
+        clock: &'a dyn Clock,
+        password_policy: &'a PasswordPolicy,
+        login_policy: &'a LoginPolicy,
+    ) -> Self {
+        Self {
+            user_repo,
+            task_repo,
+            login_attempt_repo,
+            clock,
+            password_policy,
+            login_policy,
+            tokens: HashMap::new(),
+        }
+    }
+
+    pub fn serve(&mut self, bind: &str) -> Result<(), Error> {
+        let server = tiny_http::Server::http(bind)
+            .map_err(|err| Error::Internal(format!("failed to bind {}: {}", bind, err)))?;
+        for mut request in server.incoming_requests() {
+            let response = self.respond(&mut request);
+            let _ = request.respond(response);
+        }
+
+        Ok(())
+    }
+
+    pub fn respond(&mut self, request: &mut Request) -> Response<Cursor<Vec<u8>>> {
+        let (status, body, retry_after) = match self.handle(request) {
+            Ok((status, body)) => (status, body, None),
+            Err(err) => (status(&err), Json::error(&err), err.retry_after()),
+        };
+        let body = match body {
+            Value::Null => String::new(),
+            body => body.to_string(),
+        };
+        let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+        let mut response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(header);
+        if let Some(retry_after) = retry_after {
+            let secs = retry_after.num_seconds().to_string();
+            response.add_header(Header::from_bytes(&b"Retry-After"[..], secs.as_bytes()).unwrap());
+        }
+
+        response
+    }
+
+    fn handle(&mut self, request: &mut Request) -> Result<(u16, Value), Error> {
+        let url = request.url().to_string();
+        let (path, query) = match url.find('?') {
+            Some(at) => (&url[..at], &url[at + 1..]),
+            None => (url.as_str(), ""),
+        };
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        let method = request.method().clone();
+        match (method, segments.as_slice()) {
+            (Method::Post, ["users"]) => self.create_user(request),
+            (Method::Delete, ["users", "me"]) => self.delete_user(request),
+            (Method::Post, ["login"]) => self.authenticate_user(request),
+            (Method::Post, ["logout"]) => self.deauthenticate_user(request),
+            (Method::Get, ["tasks"]) => self.get_tasks(request, query),
+            (Method::Post, ["tasks"]) => self.create_task(request),
+            (Method::Get, ["tasks", id]) => self.get_task(request, id),
+            (Method::Patch, ["tasks", id]) => self.edit_task(request, id),
+            (Method::Post, ["tasks", id, "complete"]) => self.complete_task(request, id),
+            (Method::Delete, ["tasks", id]) => self.delete_task(request, id),
+            _ => Err(Error::NotFound(format!(
+                "no such endpoint: {} {}",
+                request.method(),
+                path
+            ))),
+        }
+    }
+
+    fn create_user(&mut self, request: &mut Request) -> Result<(u16, Value), Error> {
+        let body: Credentials = read_body(request)?;
+        let user = usecase::CreateUser::new(self.user_repo, self.clock, self.password_policy)
+            .invoke(&body.email, &body.password)?;
+
+        Ok((201, Json::user(&user)))
+    }
+
+    fn delete_user(&mut self, request: &mut Request) -> Result<(u16, Value), Error> {
+        let user_id = self.authenticated_user_id(request)?;
+        usecase::DeleteUser::new(self.user_repo, self.task_repo).invoke(&user_id)?;
+        self.tokens.retain(|_, id| *id != user_id);
+
+        Ok((204, Value::Null))
+    }
+
+    fn authenticate_user(&mut self, request: &mut Request) -> Result<(u16, Value), Error> {
+        let body: Credentials = read_body(request)?;
+        let user = match usecase::AuthenticateUser::new(
+            self.user_repo.as_ref(),
+            self.login_attempt_repo,
+            self.clock,
+            self.login_policy,
+        )
+        .invoke(&body.email, &body.password)?
+        {
+            Some(user) => user,
+            None => return Err(Error::Unauthorized(String::from("invalid credentials"))),
+        };
+
+        let token: String = rand::thread_rng()
+            .sample_iter(rand::distributions::Alphanumeric)
+            .take(40)
+            .collect();
+        self.tokens.insert(token.clone(), user.id().to_string());
+
+        Ok((200, json!({ "token": token, "user": Json::user(&user) })))
+    }
+
+    fn deauthenticate_user(&mut self, request: &mut Request) -> Result<(u16, Value), Error> {
+        self.authenticated_user_id(request)?;
+        if let Some(token) = bearer_token(request) {
+            self.tokens.remove(&token);
+        }
+
+        Ok((204, Value::Null))
+    }
+
+    fn get_tasks(&mut self, request: &mut Request, query: &str) -> Result<(u16, Value), Error> {
+        let user_id = self.authenticated_user_id(request)?;
+        let query = task_query(query)?;
+        let tasks = usecase::QueryTasks::new(self.task_repo.as_ref()).invoke(&user_id, &query)?;
+
+        Ok((200, tasks.iter().map(Json::task).collect()))
+    }
+
+    fn get_task(&mut self, request: &mut Request, id: &str) -> Result<(u16, Value), Error> {
+        let user_id = self.authenticated_user_id(request)?;
+        let task = usecase::GetTask::new(self.task_repo.as_ref()).invoke(id, &user_id)?;
+
+        Ok((200, Json::task(&task)))
+    }
+
+    fn create_task(&mut self, request: &mut Request) -> Result<(u16, Value), Error> {
+        let user_id = self.authenticated_user_id(request)?;
+        let body: params::NewTask = read_body(request)?;
+        let (priority, tags) = (body.priority()?, body.tags()?);
+        let mut task = usecase::CreateTask::new(self.task_repo, self.clock)
+            .invoke(&user_id, &body.name, body.due, priority)?;
+        if !tags.is_empty() {
+            let update = usecase::TaskUpdate {
+                add_tags: tags,
+                ..Default::default()
+            };
+            task = usecase::UpdateTask::new(self.task_repo, self.clock).invoke(
+                task.id(),
+                &user_id,
+                &update,
+            )?;
+        }
+
+        Ok((201, Json::task(&task)))
+    }
+
+    fn edit_task(&mut self, request: &mut Request, id: &str) -> Result<(u16, Value), Error> {
+        let user_id = self.authenticated_user_id(request)?;
+        let update = read_body::<params::TaskPatch>(request)?.into_update()?;
+        let task =
+            usecase::UpdateTask::new(self.task_repo, self.clock).invoke(id, &user_id, &update)?;
+
+        Ok((200, Json::task(&task)))
+    }
+
+    fn complete_task(&mut self, request: &mut Request, id: &str) -> Result<(u16, Value), Error> {
+        let user_id = self.authenticated_user_id(request)?;
+        let task = usecase::CompleteTask::new(self.task_repo, self.clock).invoke(id, &user_id)?;
+
+        Ok((200, Json::task(&task)))
+    }
+
+    fn delete_task(&mut self, request: &mut Request, id: &str) -> Result<(u16, Value), Error> {
+        let user_id = self.authenticated_user_id(request)?;
+        usecase::DeleteTask::new(self.task_repo).invoke(id, &user_id)?;
+
+        Ok((204, Value::Null))
+    }
+
+    fn authenticated_user_id(&self, request: &Request) -> Result<String, Error> {
+        bearer_token(request)
+            .and_then(|token| self.tokens.get(&token).cloned())
+            .ok_or_else(|| Error::Unauthorized(String::from("authentication is required")))
+    }
+}
+
+#[derive(Deserialize)]
+struct Credentials {
+    email: String,
+    password: String,
+}
+
+fn read_body<T: DeserializeOwned>(request: &mut Request) -> Result<T, Error> {
+    serde_json::from_reader(request.as_reader())
+        .map_err(|err| Error::Validation(format!("invalid body: {}", err)))
+}
+
+fn bearer_token(request: &Request) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .and_then(|header| {
+            header
+                .value
+                .as_str()
+                .strip_prefix("Bearer ")
+                .map(|token| token.trim().to_string())
+        })
+}
+
+pub fn task_query(query: &str) -> Result<TaskQuery, Error> {
+    let mut filter = params::TaskFilter::default();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = match pair.find('=') {
+            Some(at) => (&pair[..at], decode(&pair[at + 1..])?),
+            None => (pair, String::new()),
+        };
+        let invalid = || Error::Validation(format!("invalid {}: {}", key, value));
+        match key {
+            "completed" => filter.completed = Some(value.parse().map_err(|_| invalid())?),
+            "name" => filter.name = Some(value.clone()),
+            "tag" => filter.tags.push(value.clone()),
+            "any_tag" => filter.any_tag = value.is_empty() || value == "true",
+            "due_before" => filter.due_before = Some(parse_time(&value).ok_or_else(invalid)?),
+            "due_after" => filter.due_after = Some(parse_time(&value).ok_or_else(invalid)?),
+            "sort" => filter.sort = Some(value.clone()),
+            "order" => filter.order = Some(value.clone()),
+            "limit" => filter.limit = Some(value.parse().map_err(|_| invalid())?),
+            "offset" => filter.offset = Some(value.parse().map_err(|_| invalid())?),
+            _ => {
+                return Err(Error::Validation(format!(
+                    "unknown query parameter: {}",
+                    key
+                )))
+            }
+        }
+    }
+
+    filter.into_query()
+}
+
+fn parse_time(time: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(time)
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+pub fn decode(s: &str) -> Result<String, Error> {
+    let invalid = || Error::Validation(format!("invalid query encoding: {}", s));
+    let mut bytes = Vec::new();
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        match b {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [
+                    iter.next().ok_or_else(invalid)?,
+                    iter.next().ok_or_else(invalid)?,
+                ];
+                if !hex.iter().all(u8::is_ascii_hexdigit) {
+                    return Err(invalid());
+                }
+                let hex = std::str::from_utf8(&hex).map_err(|_| invalid())?;
+                bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            }
+            b => bytes.push(b),
+        }
+    }
+
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+fn status(err: &Error) -> u16 {
+    match err {
+        Error::Validation(_) => 400,
+        Error::Unauthorized(_) => 401,
+        Error::NotFound(_) => 404,
+        Error::Conflict(_) => 409,
+        Error::TooManyAttempts(..) => 429,
+        Error::Storage(_) | Error::Internal(_) => 500,
+    }
+}
@@ -1,24 +1,102 @@
 pub mod cli;
+pub mod http;
+pub mod params;
+pub mod rpc;
+pub mod shell;
+pub mod tui;
 
-use super::super::{Task, User};
-use std::error;
+use super::super::{Error, Task, User};
+use chrono::{DateTime, Duration, Utc};
+
+pub const DEFAULT_SESSION: &str = "default";
 
 pub trait UserRenderer: Renderer {
     fn render_user(&self, user: &User);
+    fn render_sessions(&self, sessions: &[Session]);
 }
 
 pub trait TaskRenderer: Renderer {
-    fn render_tasks(&self, tasks: &Vec<Task>);
+    fn render_tasks(&self, tasks: &[Task]);
     fn render_task(&self, task: &Task);
 }
 
 pub trait Renderer {
     fn render_message(&self, msg: &str);
-    fn render_error(&self, msg: &str);
+    fn render_error(&self, err: &Error);
 }
 
 pub trait SessionManager {
-    fn push_authenticated_user_id(&mut self, user_id: &str) -> Result<(), Box<dyn error::Error>>;
-    fn pop_authenticated_user_id(&self) -> Result<Option<String>, Box<dyn error::Error>>;
-    fn drop_authenticated_user_id(&mut self) -> Result<(), Box<dyn error::Error>>;
+    fn select_session(&mut self, name: &str);
+    fn push_authenticated_user_id(&mut self, user_id: &str) -> Result<(), Error>;
+    fn pop_authenticated_user_id(&self) -> Result<Option<String>, Error>;
+    fn drop_authenticated_user_id(&mut self) -> Result<(), Error>;
+    fn find_sessions_of_user(&self, user_id: &str) -> Result<Vec<Session>, Error>;
+    fn drop_sessions_of_user(&mut self, user_id: &str) -> Result<(), Error>;
+    fn drop_session_records_of_user(&mut self, user_id: &str) -> Result<(), Error>;
+    fn drop_other_sessions_of_user(&mut self, user_id: &str) -> Result<(), Error>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub name: String,
+    pub token_hash: String,
+    pub user_id: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub idle_timeout: Duration,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Session {
+    pub fn new(
+        name: &str,
+        token_hash: &str,
+        user_id: &str,
+        now: DateTime<Utc>,
+        policy: &SessionPolicy,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            token_hash: token_hash.to_string(),
+            user_id: user_id.to_string(),
+            created_at: now,
+            last_used_at: now,
+            idle_timeout: policy.idle_timeout,
+            expires_at: now + policy.lifetime,
+        }
+    }
+
+    pub fn valid_until(&self) -> DateTime<Utc> {
+        self.expires_at.min(self.last_used_at + self.idle_timeout)
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now >= self.valid_until()
+    }
+
+    // Recording every use would rewrite the store on each command, so last_used_at only moves
+    // once it is a minute old; the idle timeout is that much less precise.
+    pub fn touch(&mut self, now: DateTime<Utc>) -> bool {
+        if now - self.last_used_at < Duration::minutes(1) {
+            return false;
+        }
+        self.last_used_at = now;
+
+        true
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionPolicy {
+    pub idle_timeout: Duration,
+    pub lifetime: Duration,
+}
+
+impl Default for SessionPolicy {
+    fn default() -> Self {
+        Self {
+            idle_timeout: Duration::days(7),
+            lifetime: Duration::days(30),
+        }
+    }
 }
@@ -0,0 +1,127 @@
+use super::super::super::usecase;
+use super::super::super::{
+    Error, Priority, SortDirection, TagFilter, Task, TaskQuery, TaskSort, TaskSortKey,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer};
+
+#[derive(Deserialize)]
+pub struct NewTask {
+    pub name: String,
+    #[serde(default)]
+    pub due: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub priority: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl NewTask {
+    pub fn priority(&self) -> Result<Priority, Error> {
+        match &self.priority {
+            Some(priority) => Priority::new(priority),
+            None => Ok(Priority::None),
+        }
+    }
+
+    pub fn tags(&self) -> Result<Vec<String>, Error> {
+        self.tags
+            .iter()
+            .map(|tag| Task::normalize_tag(tag))
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+pub struct TaskPatch {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub completed: Option<bool>,
+    #[serde(default, deserialize_with = "present")]
+    pub due: Option<Option<DateTime<Utc>>>,
+    #[serde(default)]
+    pub priority: Option<String>,
+    #[serde(default)]
+    pub add_tags: Vec<String>,
+    #[serde(default)]
+    pub remove_tags: Vec<String>,
+}
+
+impl TaskPatch {
+    pub fn into_update(self) -> Result<usecase::TaskUpdate, Error> {
+        Ok(usecase::TaskUpdate {
+            name: self.name,
+            completed: self.completed,
+            due: self.due,
+            priority: match &self.priority {
+                Some(priority) => Some(Priority::new(priority)?),
+                None => None,
+            },
+            add_tags: self.add_tags,
+            remove_tags: self.remove_tags,
+        })
+    }
+}
+
+#[derive(Deserialize, Default)]
+pub struct TaskFilter {
+    #[serde(default)]
+    pub completed: Option<bool>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub any_tag: bool,
+    #[serde(default)]
+    pub due_before: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub due_after: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub sort: Option<String>,
+    #[serde(default)]
+    pub order: Option<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: Option<usize>,
+}
+
+impl TaskFilter {
+    pub fn into_query(self) -> Result<TaskQuery, Error> {
+        let mut query = TaskQuery {
+            completed: self.completed,
+            name_contains: self.name,
+            due_before: self.due_before,
+            due_after: self.due_after,
+            limit: self.limit,
+            offset: self.offset.unwrap_or_default(),
+            ..Default::default()
+        };
+        if !self.tags.is_empty() {
+            let tags: Vec<&str> = self.tags.iter().map(String::as_str).collect();
+            query.tags = Some(if self.any_tag {
+                TagFilter::any(&tags)?
+            } else {
+                TagFilter::all(&tags)?
+            });
+        }
+        if let Some(key) = &self.sort {
+            query.sort = TaskSort::new(TaskSortKey::new(key)?);
+        }
+        if let Some(direction) = &self.order {
+            query.sort.direction = SortDirection::new(direction)?;
+        }
+
+        Ok(query)
+    }
+}
+
+pub fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
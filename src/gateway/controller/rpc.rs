@@ -0,0 +1,249 @@
+extern crate serde_json;
+
+use super::super::super::usecase;
+use super::super::super::{Clock, Error, TaskRepo};
+use super::super::controller;
+use super::super::presenter::json::Json;
+use super::params;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+pub struct Server<'a> {
+    task_repo: &'a mut Box<dyn TaskRepo>,
+    session_manager: &'a dyn controller::SessionManager,
+    clock: &'a dyn Clock,
+}
+
+impl<'a> Server<'a> {
+    pub fn new(
+        task_repo: &'a mut Box<dyn TaskRepo>,
+        session_manager: &'a dyn controller::SessionManager,
+        clock: &'a dyn Clock,
+    ) -> Self {
+        Self {
+            task_repo,
+            session_manager,
+            clock,
+        }
+    }
+
+    pub fn serve<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> Result<(), Error> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let response = match serde_json::from_str::<Value>(&line) {
+                Ok(Value::Array(requests)) if !requests.is_empty() => {
+                    let responses: Vec<Value> = requests
+                        .into_iter()
+                        .filter_map(|request| self.handle(request))
+                        .collect();
+                    if responses.is_empty() {
+                        None
+                    } else {
+                        Some(Value::from(responses))
+                    }
+                }
+                Ok(request) => self.handle(request),
+                Err(err) => Some(failure(Value::Null, PARSE_ERROR, &err.to_string(), None)),
+            };
+            if let Some(response) = response {
+                writeln!(output, "{}", response)?;
+                output.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle(&mut self, request: Value) -> Option<Value> {
+        let request: Request = match serde_json::from_value(request) {
+            Ok(request) => request,
+            Err(err) => {
+                return Some(failure(
+                    Value::Null,
+                    INVALID_REQUEST,
+                    &err.to_string(),
+                    None,
+                ))
+            }
+        };
+        if request.jsonrpc != "2.0" {
+            return Some(failure(
+                request.id.unwrap_or(Value::Null),
+                INVALID_REQUEST,
+                "jsonrpc should be 2.0",
+                None,
+            ));
+        }
+
+        let result = self.call(&request.method, request.params);
+        let id = request.id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+            Err(Failure::Method) => failure(id, METHOD_NOT_FOUND, "method not found", None),
+            Err(Failure::Params(msg)) => failure(id, INVALID_PARAMS, &msg, None),
+            Err(Failure::Error(err)) => failure(
+                id,
+                code(&err),
+                err.message(),
+                Some(json!({ "kind": err.kind() })),
+            ),
+        })
+    }
+
+    fn call(&mut self, method: &str, params: Value) -> Result<Value, Failure> {
+        match method {
+            "task.query" => self.query_tasks(params),
+            "task.get" => self.get_task(params),
+            "task.create" => self.create_task(params),
+            "task.edit" => self.edit_task(params),
+            "task.complete" => self.complete_task(params),
+            "task.delete" => self.delete_task(params),
+            _ => Err(Failure::Method),
+        }
+    }
+
+    fn query_tasks(&mut self, params: Value) -> Result<Value, Failure> {
+        let user_id = self.authenticated_user_id()?;
+        let query = match params {
+            Value::Null => params::TaskFilter::default(),
+            params => parse_params(params)?,
+        }
+        .into_query()?;
+        let tasks = usecase::QueryTasks::new(self.task_repo.as_ref()).invoke(&user_id, &query)?;
+
+        Ok(tasks.iter().map(Json::task).collect())
+    }
+
+    fn get_task(&mut self, params: Value) -> Result<Value, Failure> {
+        let user_id = self.authenticated_user_id()?;
+        let params: TaskId = parse_params(params)?;
+        let task = usecase::GetTask::new(self.task_repo.as_ref()).invoke(&params.id, &user_id)?;
+
+        Ok(Json::task(&task))
+    }
+
+    fn create_task(&mut self, params: Value) -> Result<Value, Failure> {
+        let user_id = self.authenticated_user_id()?;
+        let params: params::NewTask = parse_params(params)?;
+        let (priority, tags) = (params.priority()?, params.tags()?);
+        let mut task = usecase::CreateTask::new(self.task_repo, self.clock).invoke(
+            &user_id,
+            &params.name,
+            params.due,
+            priority,
+        )?;
+        if !tags.is_empty() {
+            let update = usecase::TaskUpdate {
+                add_tags: tags,
+                ..Default::default()
+            };
+            task = usecase::UpdateTask::new(self.task_repo, self.clock).invoke(
+                task.id(),
+                &user_id,
+                &update,
+            )?;
+        }
+
+        Ok(Json::task(&task))
+    }
+
+    fn edit_task(&mut self, params: Value) -> Result<Value, Failure> {
+        let user_id = self.authenticated_user_id()?;
+        let id: TaskId = parse_params(params.clone())?;
+        let update = parse_params::<params::TaskPatch>(params)?.into_update()?;
+        let task = usecase::UpdateTask::new(self.task_repo, self.clock)
+            .invoke(&id.id, &user_id, &update)?;
+
+        Ok(Json::task(&task))
+    }
+
+    fn complete_task(&mut self, params: Value) -> Result<Value, Failure> {
+        let user_id = self.authenticated_user_id()?;
+        let params: TaskId = parse_params(params)?;
+        let task =
+            usecase::CompleteTask::new(self.task_repo, self.clock).invoke(&params.id, &user_id)?;
+
+        Ok(Json::task(&task))
+    }
+
+    fn delete_task(&mut self, params: Value) -> Result<Value, Failure> {
+        let user_id = self.authenticated_user_id()?;
+        let params: TaskId = parse_params(params)?;
+        usecase::DeleteTask::new(self.task_repo).invoke(&params.id, &user_id)?;
+
+        Ok(Value::Null)
+    }
+
+    fn authenticated_user_id(&self) -> Result<String, Error> {
+        match self.session_manager.pop_authenticated_user_id()? {
+            Some(user_id) => Ok(user_id),
+            None => Err(Error::Unauthorized(String::from(
+                "authentication is required",
+            ))),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: String,
+    method: String,
+    #[serde(default)]
+    params: Value,
+    // A null id still asks for a response; only a missing one makes a notification.
+    #[serde(default, deserialize_with = "params::present")]
+    id: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct TaskId {
+    id: String,
+}
+
+enum Failure {
+    Method,
+    Params(String),
+    Error(Error),
+}
+
+impl From<Error> for Failure {
+    fn from(err: Error) -> Self {
+        Failure::Error(err)
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, Failure> {
+    serde_json::from_value(params).map_err(|err| Failure::Params(err.to_string()))
+}
+
+fn failure(id: Value, code: i64, message: &str, data: Option<Value>) -> Value {
+    let mut error = json!({ "code": code, "message": message });
+    if let Some(data) = data {
+        error["data"] = data;
+    }
+
+    json!({ "jsonrpc": "2.0", "error": error, "id": id })
+}
+
+fn code(err: &Error) -> i64 {
+    match err {
+        Error::Validation(_) => INVALID_PARAMS,
+        Error::NotFound(_) => -32001,
+        Error::Unauthorized(_) => -32002,
+        Error::Conflict(_) => -32003,
+        Error::Storage(_) => -32004,
+        Error::TooManyAttempts(..) => -32005,
+        Error::Internal(_) => -32603,
+    }
+}
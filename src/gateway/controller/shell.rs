@@ -0,0 +1,144 @@
+extern crate rustyline;
+
+use super::super::super::Error;
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::Context;
+
+const TASK_ID_OPTIONS: [&str; 2] = ["--id", "--before"];
+const SECRET_OPTIONS: [&str; 2] = ["--password", "--new-password"];
+
+pub struct Helper {
+    commands: Command,
+    task_ids: Vec<String>,
+}
+
+impl Helper {
+    pub fn new(commands: Command) -> Self {
+        Self {
+            commands,
+            task_ids: Vec::new(),
+        }
+    }
+
+    pub fn set_task_ids(&mut self, task_ids: Vec<String>) {
+        self.task_ids = task_ids;
+    }
+
+    fn candidates(&self, words: &[&str], word: &str) -> Vec<String> {
+        if words
+            .last()
+            .is_some_and(|last| TASK_ID_OPTIONS.contains(last))
+        {
+            return starting_with(self.task_ids.iter(), word);
+        }
+        if word.starts_with('-') {
+            return Vec::new();
+        }
+
+        let mut command = &self.commands;
+        for word in words.iter().filter(|word| !word.starts_with('-')) {
+            match command.subcommands.iter().find(|sub| sub.name == *word) {
+                Some(sub) => command = sub,
+                None => return Vec::new(),
+            }
+        }
+
+        starting_with(command.subcommands.iter().map(|sub| &sub.name), word)
+    }
+}
+
+impl Completer for Helper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |at| at + 1);
+        let words: Vec<&str> = line[..start].split_whitespace().collect();
+        let candidates = self
+            .candidates(&words, &line[start..])
+            .into_iter()
+            .map(|candidate| Pair {
+                display: candidate.clone(),
+                replacement: candidate,
+            })
+            .collect();
+
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for Helper {
+    type Hint = String;
+}
+
+impl Highlighter for Helper {}
+
+impl Validator for Helper {}
+
+impl rustyline::Helper for Helper {}
+
+pub struct Command {
+    name: String,
+    subcommands: Vec<Command>,
+}
+
+impl Command {
+    pub fn new(name: &str, subcommands: Vec<Command>) -> Self {
+        Self {
+            name: name.to_string(),
+            subcommands,
+        }
+    }
+}
+
+pub fn split_words(line: &str) -> Result<Vec<String>, Error> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => match chars.next() {
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => return Err(Error::Validation(String::from("trailing \\"))),
+            },
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if let Some(q) = quote {
+        return Err(Error::Validation(format!("unclosed {}", q)));
+    }
+    words.extend(word);
+
+    Ok(words)
+}
+
+pub fn has_secret(words: &[String]) -> bool {
+    words.iter().any(|word| {
+        SECRET_OPTIONS
+            .iter()
+            .any(|option| word.split('=').next() == Some(option))
+    })
+}
+
+fn starting_with<'a, I: Iterator<Item = &'a String>>(candidates: I, prefix: &str) -> Vec<String> {
+    candidates
+        .filter(|candidate| candidate.starts_with(prefix))
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,309 @@
+extern crate crossterm;
+extern crate unicode_width;
+
+use super::super::super::usecase;
+use super::super::super::{Clock, Error, Priority, Task, TaskQuery, TaskRepo};
+use super::super::controller;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use std::io::{self, Write};
+use std::mem;
+use unicode_width::UnicodeWidthChar;
+
+pub struct Tui<'a> {
+    task_repo: &'a mut Box<dyn TaskRepo>,
+    session_manager: &'a dyn controller::SessionManager,
+    clock: &'a dyn Clock,
+    tasks: Vec<Task>,
+    cursor: usize,
+    filter: String,
+    mode: Mode,
+    message: String,
+}
+
+enum Mode {
+    Browse,
+    Filter,
+    Rename(String),
+    Create(String),
+    Delete,
+}
+
+impl<'a> Tui<'a> {
+    pub fn new(
+        task_repo: &'a mut Box<dyn TaskRepo>,
+        session_manager: &'a dyn controller::SessionManager,
+        clock: &'a dyn Clock,
+    ) -> Self {
+        Self {
+            task_repo,
+            session_manager,
+            clock,
+            tasks: Vec::new(),
+            cursor: 0,
+            filter: String::new(),
+            mode: Mode::Browse,
+            message: String::new(),
+        }
+    }
+
+    pub fn run(&mut self) -> Result<(), Error> {
+        let user_id = match self.session_manager.pop_authenticated_user_id()? {
+            Some(user_id) => user_id,
+            None => {
+                return Err(Error::Unauthorized(String::from(
+                    "authentication is required",
+                )))
+            }
+        };
+
+        let _screen = Screen::enter()?;
+        let mut stdout = io::stdout();
+        loop {
+            self.load(&user_id)?;
+            self.draw(&mut stdout)?;
+
+            if let Event::Key(key) = event::read()? {
+                if key.code == KeyCode::Char('c') && key.modifiers == KeyModifiers::CONTROL {
+                    return Ok(());
+                }
+                self.message.clear();
+                match self.handle(&user_id, key) {
+                    Ok(true) => return Ok(()),
+                    Ok(false) => {}
+                    Err(err) => self.message = err.to_string(),
+                }
+            }
+        }
+    }
+
+    fn load(&mut self, user_id: &str) -> Result<(), Error> {
+        let query = TaskQuery {
+            name_contains: Some(self.filter.clone()).filter(|filter| !filter.is_empty()),
+            ..Default::default()
+        };
+        self.tasks = usecase::QueryTasks::new(self.task_repo.as_ref()).invoke(user_id, &query)?;
+        self.cursor = self.cursor.min(self.tasks.len().saturating_sub(1));
+
+        Ok(())
+    }
+
+    fn handle(&mut self, user_id: &str, key: KeyEvent) -> Result<bool, Error> {
+        match &mut self.mode {
+            Mode::Browse => return self.browse(user_id, key.code),
+            Mode::Filter => {
+                match key.code {
+                    KeyCode::Enter => self.mode = Mode::Browse,
+                    KeyCode::Esc => {
+                        self.filter.clear();
+                        self.mode = Mode::Browse;
+                    }
+                    KeyCode::Backspace => {
+                        self.filter.pop();
+                    }
+                    KeyCode::Char(c) => self.filter.push(c),
+                    _ => {}
+                }
+                self.cursor = 0;
+            }
+            Mode::Rename(buffer) | Mode::Create(buffer) => match key.code {
+                KeyCode::Enter => self.commit(user_id)?,
+                KeyCode::Esc => self.mode = Mode::Browse,
+                KeyCode::Backspace => {
+                    buffer.pop();
+                }
+                KeyCode::Char(c) => buffer.push(c),
+                _ => {}
+            },
+            Mode::Delete => {
+                self.mode = Mode::Browse;
+                if key.code == KeyCode::Char('y') {
+                    self.delete(user_id)?;
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn browse(&mut self, user_id: &str, code: KeyCode) -> Result<bool, Error> {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(true),
+            KeyCode::Char('j') | KeyCode::Down if self.cursor + 1 < self.tasks.len() => {
+                self.cursor += 1
+            }
+            KeyCode::Char('k') | KeyCode::Up => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Char(' ') | KeyCode::Char('x') => self.toggle(user_id)?,
+            KeyCode::Char('r') => {
+                if let Some(task) = self.tasks.get(self.cursor) {
+                    self.mode = Mode::Rename(task.name().to_string());
+                }
+            }
+            KeyCode::Char('a') => self.mode = Mode::Create(String::new()),
+            KeyCode::Char('d') if !self.tasks.is_empty() => self.mode = Mode::Delete,
+            KeyCode::Char('/') => self.mode = Mode::Filter,
+            _ => {}
+        }
+
+        Ok(false)
+    }
+
+    fn commit(&mut self, user_id: &str) -> Result<(), Error> {
+        match mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Rename(name) => self.rename(user_id, &name),
+            Mode::Create(name) => self.create(user_id, &name),
+            _ => Ok(()),
+        }
+    }
+
+    fn toggle(&mut self, user_id: &str) -> Result<(), Error> {
+        let task = match self.tasks.get(self.cursor) {
+            Some(task) => task,
+            None => return Ok(()),
+        };
+        let update = usecase::TaskUpdate {
+            completed: Some(!task.is_completed()),
+            ..Default::default()
+        };
+        usecase::UpdateTask::new(self.task_repo, self.clock).invoke(task.id(), user_id, &update)?;
+
+        Ok(())
+    }
+
+    fn rename(&mut self, user_id: &str, name: &str) -> Result<(), Error> {
+        let task = match self.tasks.get(self.cursor) {
+            Some(task) => task,
+            None => return Ok(()),
+        };
+        let update = usecase::TaskUpdate {
+            name: Some(name.to_string()),
+            ..Default::default()
+        };
+        usecase::UpdateTask::new(self.task_repo, self.clock).invoke(task.id(), user_id, &update)?;
+
+        Ok(())
+    }
+
+    fn create(&mut self, user_id: &str, name: &str) -> Result<(), Error> {
+        usecase::CreateTask::new(self.task_repo, self.clock).invoke(
+            user_id,
+            name,
+            None,
+            Priority::None,
+        )?;
+        self.message = String::from("The task is created.");
+
+        Ok(())
+    }
+
+    fn delete(&mut self, user_id: &str) -> Result<(), Error> {
+        let task = match self.tasks.get(self.cursor) {
+            Some(task) => task,
+            None => return Ok(()),
+        };
+        usecase::DeleteTask::new(self.task_repo).invoke(task.id(), user_id)?;
+        self.message = String::from("The task is deleted.");
+
+        Ok(())
+    }
+
+    fn draw<W: Write>(&self, out: &mut W) -> Result<(), Error> {
+        let (width, height) = terminal::size()?;
+        let (width, height) = (width as usize, height as usize);
+        queue!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+
+        let header = match (&self.mode, self.filter.is_empty()) {
+            (Mode::Filter, _) => format!("Filter: {}", self.filter),
+            (_, true) => format!("{} tasks", self.tasks.len()),
+            (_, false) => format!("{} tasks matching \"{}\"", self.tasks.len(), self.filter),
+        };
+        queue!(
+            out,
+            SetAttribute(Attribute::Bold),
+            Print(fit(&header, width)),
+            SetAttribute(Attribute::Reset)
+        )?;
+
+        let rows = height.saturating_sub(3);
+        let top = (self.cursor + 1).saturating_sub(rows);
+        for (i, task) in self.tasks.iter().enumerate().skip(top).take(rows) {
+            let line = format!(
+                "{} {}",
+                if task.is_completed() { "[x]" } else { "[ ]" },
+                task.name()
+            );
+            queue!(out, MoveTo(0, (i - top + 2) as u16))?;
+            if i == self.cursor {
+                queue!(
+                    out,
+                    SetAttribute(Attribute::Reverse),
+                    Print(fit(&line, width)),
+                    SetAttribute(Attribute::Reset)
+                )?;
+            } else {
+                queue!(out, Print(fit(&line, width)))?;
+            }
+        }
+
+        let status = match &self.mode {
+            Mode::Rename(buffer) => format!("Rename: {}", buffer),
+            Mode::Create(buffer) => format!("New task: {}", buffer),
+            Mode::Delete => String::from("Delete this task? (y/N)"),
+            _ if !self.message.is_empty() => self.message.clone(),
+            _ => String::from(
+                "j/k: move  space: complete  a: add  r: rename  d: delete  /: filter  q: quit",
+            ),
+        };
+        queue!(
+            out,
+            MoveTo(0, height.saturating_sub(1) as u16),
+            Print(fit(&status, width))
+        )?;
+        out.flush()?;
+
+        Ok(())
+    }
+}
+
+impl From<crossterm::ErrorKind> for Error {
+    fn from(err: crossterm::ErrorKind) -> Self {
+        Error::Internal(err.to_string())
+    }
+}
+
+struct Screen {}
+
+impl Screen {
+    fn enter() -> Result<Self, Error> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+
+        Ok(Self {})
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+// Wide characters such as CJK take two columns, so lines are cut by display width to stay on one
+// row.
+pub fn fit(s: &str, width: usize) -> String {
+    let mut fitted = String::new();
+    let mut used = 0;
+    for c in s.chars() {
+        used += c.width().unwrap_or(0);
+        if used > width {
+            break;
+        }
+        fitted.push(c);
+    }
+
+    fitted
+}
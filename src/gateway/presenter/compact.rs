@@ -0,0 +1,39 @@
+use super::super::super::{Error, Task};
+use super::super::controller;
+use super::{checkbox, truncate};
+
+pub struct Compact {
+    width: Option<usize>,
+}
+
+impl Compact {
+    pub fn new(width: Option<usize>) -> Self {
+        Self { width }
+    }
+}
+
+impl controller::TaskRenderer for Compact {
+    fn render_tasks(&self, tasks: &[Task]) {
+        for task in tasks {
+            self.render_task(task);
+        }
+    }
+
+    fn render_task(&self, task: &Task) {
+        let line = format!("{} {}", checkbox(task.is_completed()), task.name());
+        match self.width {
+            Some(width) => println!("{}", truncate(&line, width)),
+            None => println!("{}", line),
+        }
+    }
+}
+
+impl controller::Renderer for Compact {
+    fn render_message(&self, msg: &str) {
+        println!("{}", msg);
+    }
+
+    fn render_error(&self, err: &Error) {
+        eprintln!("{}", err);
+    }
+}
@@ -0,0 +1,87 @@
+extern crate serde_json;
+
+use super::super::super::{Error, Task, User};
+use super::super::controller;
+use serde_json::{json, Value};
+
+pub struct Json {}
+
+impl Json {
+    pub fn user(user: &User) -> Value {
+        json!({
+            "id": user.id(),
+            "email": user.email(),
+            "created_at": user.created_at(),
+        })
+    }
+
+    pub fn task(task: &Task) -> Value {
+        json!({
+            "id": task.id(),
+            "user_id": task.user_id(),
+            "name": task.name(),
+            "completed": task.is_completed(),
+            "due": task.due(),
+            "priority": task.priority().name(),
+            "tags": task.tags(),
+            "position": task.position(),
+            "created_at": task.created_at(),
+            "updated_at": task.updated_at(),
+            "completed_at": task.completed_at(),
+        })
+    }
+
+    pub fn session(session: &controller::Session) -> Value {
+        json!({
+            "name": session.name,
+            "user_id": session.user_id,
+            "created_at": session.created_at,
+            "last_used_at": session.last_used_at,
+            "expires_at": session.valid_until(),
+        })
+    }
+
+    pub fn error(err: &Error) -> Value {
+        let mut error = json!({
+            "kind": err.kind(),
+            "message": err.message(),
+        });
+        if let Some(retry_after) = err.retry_after() {
+            error["retry_after"] = json!(retry_after.num_seconds());
+        }
+
+        json!({ "error": error })
+    }
+}
+
+impl controller::UserRenderer for Json {
+    fn render_user(&self, user: &User) {
+        println!("{}", Self::user(user));
+    }
+
+    fn render_sessions(&self, sessions: &[controller::Session]) {
+        let sessions: Vec<Value> = sessions.iter().map(Self::session).collect();
+        println!("{}", Value::from(sessions));
+    }
+}
+
+impl controller::TaskRenderer for Json {
+    fn render_tasks(&self, tasks: &[Task]) {
+        let tasks: Vec<Value> = tasks.iter().map(Self::task).collect();
+        println!("{}", Value::from(tasks));
+    }
+
+    fn render_task(&self, task: &Task) {
+        println!("{}", Self::task(task));
+    }
+}
+
+impl controller::Renderer for Json {
+    fn render_message(&self, msg: &str) {
+        eprintln!("{}", json!({ "message": msg }));
+    }
+
+    fn render_error(&self, err: &Error) {
+        eprintln!("{}", Self::error(err));
+    }
+}
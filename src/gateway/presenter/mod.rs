@@ -1 +1,53 @@
+extern crate terminal_size;
+
+pub mod compact;
+pub mod json;
+pub mod table;
+pub mod template;
 pub mod text;
+
+use chrono::{DateTime, Local, Utc};
+use std::env;
+use terminal_size::{terminal_size, Width};
+
+pub fn terminal_width() -> Option<usize> {
+    if let Some((Width(width), _)) = terminal_size() {
+        return Some(width as usize);
+    }
+
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|width| width.parse().ok())
+}
+
+pub fn truncate(s: &str, width: usize) -> String {
+    if s.chars().count() <= width {
+        return s.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    let mut truncated: String = s.chars().take(width - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+fn short_id(id: &str) -> String {
+    id.chars().take(8).collect()
+}
+
+fn datetime(datetime: DateTime<Utc>) -> String {
+    datetime
+        .with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+fn checkbox(completed: bool) -> &'static str {
+    if completed {
+        "[x]"
+    } else {
+        "[ ]"
+    }
+}
@@ -0,0 +1,96 @@
+use super::super::super::{Error, Priority, Task};
+use super::super::controller;
+use super::{checkbox, datetime, short_id, truncate};
+
+const HEADER: [&str; 6] = ["ID", "DONE", "NAME", "DUE", "PRIORITY", "TAGS"];
+const NAME: usize = 2;
+const TAGS: usize = 5;
+const MIN_WIDTH: usize = 8;
+const GAP: &str = "  ";
+
+pub struct Table {
+    width: Option<usize>,
+}
+
+impl Table {
+    pub fn new(width: Option<usize>) -> Self {
+        Self { width }
+    }
+
+    pub fn render(&self, tasks: &[Task]) -> Vec<String> {
+        let mut rows = vec![HEADER.map(String::from)];
+        rows.extend(tasks.iter().map(Self::row));
+
+        let widths = self.widths(&rows);
+        rows.iter()
+            .map(|row| {
+                let cells: Vec<String> = row
+                    .iter()
+                    .zip(widths.iter())
+                    .map(|(cell, width)| format!("{:<1$}", truncate(cell, *width), width))
+                    .collect();
+                cells.join(GAP).trim_end().to_string()
+            })
+            .collect()
+    }
+
+    fn row(task: &Task) -> [String; 6] {
+        let tags: Vec<&str> = task.tags().iter().map(String::as_str).collect();
+        [
+            short_id(task.id()),
+            checkbox(task.is_completed()).to_string(),
+            task.name().to_string(),
+            task.due().map(datetime).unwrap_or_default(),
+            match task.priority() {
+                Priority::None => String::new(),
+                priority => priority.name().to_string(),
+            },
+            tags.join(","),
+        ]
+    }
+
+    fn widths(&self, rows: &[[String; 6]]) -> [usize; 6] {
+        let mut widths = [0; 6];
+        for row in rows {
+            for (width, cell) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let width = match self.width {
+            Some(width) => width,
+            None => return widths,
+        };
+        let total = widths.iter().sum::<usize>() + GAP.len() * (widths.len() - 1);
+        let mut overflow = total.saturating_sub(width);
+        for col in [NAME, TAGS] {
+            let shrink = overflow.min(widths[col].saturating_sub(MIN_WIDTH));
+            widths[col] -= shrink;
+            overflow -= shrink;
+        }
+
+        widths
+    }
+}
+
+impl controller::TaskRenderer for Table {
+    fn render_tasks(&self, tasks: &[Task]) {
+        for line in self.render(tasks) {
+            println!("{}", line);
+        }
+    }
+
+    fn render_task(&self, task: &Task) {
+        self.render_tasks(std::slice::from_ref(task));
+    }
+}
+
+impl controller::Renderer for Table {
+    fn render_message(&self, msg: &str) {
+        println!("{}", msg);
+    }
+
+    fn render_error(&self, err: &Error) {
+        eprintln!("{}", err);
+    }
+}
@@ -0,0 +1,185 @@
+use super::super::super::{Error, Priority, Task};
+use super::super::controller;
+use super::{checkbox, datetime, short_id};
+
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    pub fn new(template: &str) -> Result<Self, Error> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '\\' => match chars.next() {
+                    Some('n') => literal.push('\n'),
+                    Some('t') => literal.push('\t'),
+                    Some('\\') => literal.push('\\'),
+                    Some(c) => {
+                        return Err(Error::Validation(format!(
+                            "invalid template: unknown escape \\{}",
+                            c
+                        )))
+                    }
+                    None => {
+                        return Err(Error::Validation(String::from(
+                            "invalid template: trailing \\",
+                        )))
+                    }
+                },
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest.find('}').ok_or_else(|| {
+                        Error::Validation(String::from("invalid template: unclosed {"))
+                    })?;
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(literal.clone()));
+                        literal.clear();
+                    }
+                    segments.push(Segment::Field(Field::new(&rest[..end])?));
+                    chars = rest[end + 1..].chars();
+                }
+                '}' => {
+                    return Err(Error::Validation(String::from(
+                        "invalid template: unmatched }, use }} for a literal }",
+                    )))
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self { segments })
+    }
+
+    pub fn render(&self, task: &Task) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(literal) => literal.clone(),
+                Segment::Field(field) => field.value(task),
+            })
+            .collect()
+    }
+}
+
+impl controller::TaskRenderer for Template {
+    fn render_tasks(&self, tasks: &[Task]) {
+        for task in tasks {
+            self.render_task(task);
+        }
+    }
+
+    fn render_task(&self, task: &Task) {
+        println!("{}", self.render(task));
+    }
+}
+
+impl controller::Renderer for Template {
+    fn render_message(&self, msg: &str) {
+        println!("{}", msg);
+    }
+
+    fn render_error(&self, err: &Error) {
+        eprintln!("{}", err);
+    }
+}
+
+enum Segment {
+    Literal(String),
+    Field(Field),
+}
+
+#[derive(Clone, Copy)]
+enum Field {
+    Id,
+    ShortId,
+    UserId,
+    Name,
+    Status,
+    Completed,
+    Due,
+    Priority,
+    Tags,
+    Position,
+    CreatedAt,
+    UpdatedAt,
+    CompletedAt,
+}
+
+impl Field {
+    const NAMES: [&'static str; 13] = [
+        "id",
+        "short_id",
+        "user_id",
+        "name",
+        "status",
+        "completed",
+        "due",
+        "priority",
+        "tags",
+        "position",
+        "created_at",
+        "updated_at",
+        "completed_at",
+    ];
+
+    fn new(name: &str) -> Result<Self, Error> {
+        match name.trim() {
+            "id" => Ok(Field::Id),
+            "short_id" => Ok(Field::ShortId),
+            "user_id" => Ok(Field::UserId),
+            "name" => Ok(Field::Name),
+            "status" => Ok(Field::Status),
+            "completed" => Ok(Field::Completed),
+            "due" => Ok(Field::Due),
+            "priority" => Ok(Field::Priority),
+            "tags" => Ok(Field::Tags),
+            "position" => Ok(Field::Position),
+            "created_at" => Ok(Field::CreatedAt),
+            "updated_at" => Ok(Field::UpdatedAt),
+            "completed_at" => Ok(Field::CompletedAt),
+            name => Err(Error::Validation(format!(
+                "invalid template: unknown placeholder {{{}}}, expected one of {}",
+                name,
+                Self::NAMES.join(", ")
+            ))),
+        }
+    }
+
+    fn value(&self, task: &Task) -> String {
+        match self {
+            Field::Id => task.id().to_string(),
+            Field::ShortId => short_id(task.id()),
+            Field::UserId => task.user_id().to_string(),
+            Field::Name => task.name().to_string(),
+            Field::Status => checkbox(task.is_completed()).to_string(),
+            Field::Completed => task.is_completed().to_string(),
+            Field::Due => task.due().map(datetime).unwrap_or_default(),
+            Field::Priority => match task.priority() {
+                Priority::None => String::new(),
+                priority => priority.name().to_string(),
+            },
+            Field::Tags => {
+                let tags: Vec<&str> = task.tags().iter().map(String::as_str).collect();
+                tags.join(",")
+            }
+            Field::Position => task.position().to_string(),
+            Field::CreatedAt => datetime(task.created_at()),
+            Field::UpdatedAt => datetime(task.updated_at()),
+            Field::CompletedAt => task.completed_at().map(datetime).unwrap_or_default(),
+        }
+    }
+}
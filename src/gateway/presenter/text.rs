@@ -1,4 +1,4 @@
-use super::super::super::{Task, User};
+use super::super::super::{Error, Priority, Task, User};
 use super::super::controller;
 
 pub struct Text {}
@@ -7,11 +7,22 @@ impl controller::UserRenderer for Text {
     fn render_user(&self, user: &User) {
         println!("ID: {}", user.id());
         println!("Email: {}", user.email());
+        println!("Created At: {}", user.created_at());
+    }
+
+    fn render_sessions(&self, sessions: &[controller::Session]) {
+        for session in sessions {
+            println!("-----");
+            println!("Name: {}", session.name);
+            println!("Created At: {}", session.created_at);
+            println!("Last Used At: {}", session.last_used_at);
+            println!("Expires At: {}", session.valid_until());
+        }
     }
 }
 
 impl controller::TaskRenderer for Text {
-    fn render_tasks(&self, tasks: &Vec<Task>) {
+    fn render_tasks(&self, tasks: &[Task]) {
         for task in tasks {
             println!("-----");
             self.render_task(task);
@@ -30,6 +41,21 @@ impl controller::TaskRenderer for Text {
                 "Not Completed"
             }
         );
+        if let Some(due) = task.due() {
+            println!("Due: {}", due);
+        }
+        if task.priority() != Priority::None {
+            println!("Priority: {}", task.priority().name());
+        }
+        if !task.tags().is_empty() {
+            let tags: Vec<&str> = task.tags().iter().map(String::as_str).collect();
+            println!("Tags: {}", tags.join(", "));
+        }
+        println!("Created At: {}", task.created_at());
+        println!("Updated At: {}", task.updated_at());
+        if let Some(completed_at) = task.completed_at() {
+            println!("Completed At: {}", completed_at);
+        }
     }
 }
 
@@ -38,7 +64,7 @@ impl controller::Renderer for Text {
         println!("{}", msg);
     }
 
-    fn render_error(&self, msg: &str) {
-        eprintln!("{}", msg);
+    fn render_error(&self, err: &Error) {
+        eprintln!("{}", err);
     }
 }
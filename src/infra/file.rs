@@ -1,26 +1,39 @@
+extern crate fs2;
 extern crate serde;
 extern crate serde_json;
 
 use super::super::gateway::controller;
+use super::super::Clock as DomainClock;
+use super::super::Error;
 use super::super::Hash;
+use super::super::LoginAttemptRepo as DomainLoginAttemptRepo;
+use super::super::LoginAttempts as DomainLoginAttempts;
+use super::super::Priority;
 use super::super::Task as DomainTask;
+use super::super::TaskQuery;
 use super::super::TaskRepo as DomainTaskRepo;
 use super::super::User as DomainUser;
 use super::super::UserRepo as DomainUserRepo;
 use super::rand;
+use super::state::{self, TokenFile};
+use chrono::{DateTime, Duration, Utc};
+use fs2::FileExt;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::error;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
 use std::fs;
 use std::io::prelude::*;
-use std::path::Path;
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 pub struct UserRepo {
     file: File,
 }
 
 impl UserRepo {
-    pub fn new(workspace: &str) -> Result<Self, Box<dyn error::Error>> {
+    pub fn new(workspace: &str) -> Result<Self, Error> {
         Ok(Self {
             file: File::new(workspace)?,
         })
@@ -28,23 +41,51 @@ impl UserRepo {
 }
 
 impl DomainUserRepo for UserRepo {
-    fn next_id(&self) -> Result<String, Box<dyn error::Error>> {
+    fn next_id(&self) -> Result<String, Error> {
         Ok(rand::generate_string(50))
     }
 
-    fn find_by_email(&self, email: &str) -> Result<Option<DomainUser>, Box<dyn error::Error>> {
-        let store = self.file.load()?;
-        for (_, user) in &store.users {
-            if user.email == email {
-                return Ok(Some(DomainUser::from(user.clone())));
-            }
-        }
+    fn find_by_id(&self, id: &str) -> Result<Option<DomainUser>, Error> {
+        let lock = self.file.lock_shared()?;
+        let store = self.file.load(&lock)?;
 
-        Ok(None)
+        Ok(store.users.get(id).cloned().map(DomainUser::from))
+    }
+
+    fn find_by_email(&self, email: &str) -> Result<Option<DomainUser>, Error> {
+        let lock = self.file.lock_shared()?;
+        let store = self.file.load(&lock)?;
+        let email = DomainUser::email_key(email);
+        let user = store
+            .users
+            .into_values()
+            .filter(|user| DomainUser::email_key(&user.email) == email)
+            .min_by_key(|user| (user.created_at, user.id.clone()));
+
+        Ok(user.map(DomainUser::from))
+    }
+
+    fn find_all(&self) -> Result<Vec<DomainUser>, Error> {
+        let lock = self.file.lock_shared()?;
+        let store = self.file.load(&lock)?;
+        let mut users: Vec<User> = store.users.into_values().collect();
+        users.sort_by_key(|user| (user.created_at, user.id.clone()));
+
+        Ok(users.into_iter().map(DomainUser::from).collect())
     }
 
-    fn save(&mut self, user: &DomainUser) -> Result<(), Box<dyn error::Error>> {
-        let mut store = self.file.load()?;
+    fn save(&mut self, user: &DomainUser) -> Result<(), Error> {
+        let lock = self.file.lock_exclusive()?;
+        let mut store = self.file.load(&lock)?;
+        let email = DomainUser::email_key(user.email());
+        if store
+            .users
+            .values()
+            .any(|other| other.id != *user.id() && DomainUser::email_key(&other.email) == email)
+        {
+            return Err(Error::email_taken());
+        }
+
         store
             .users
             .insert(user.id().clone(), User::from(user.clone()));
@@ -52,20 +93,65 @@ impl DomainUserRepo for UserRepo {
         self.file.store(&store)
     }
 
-    fn delete(&mut self, id: &str) -> Result<(), Box<dyn error::Error>> {
-        let mut store = self.file.load()?;
+    fn delete(&mut self, id: &str) -> Result<(), Error> {
+        let lock = self.file.lock_exclusive()?;
+        let mut store = self.file.load(&lock)?;
         store.users.remove(id);
 
         self.file.store(&store)
     }
 }
 
+pub struct LoginAttemptRepo {
+    file: File,
+}
+
+impl LoginAttemptRepo {
+    pub fn new(workspace: &str) -> Result<Self, Error> {
+        Ok(Self {
+            file: File::new(workspace)?,
+        })
+    }
+}
+
+impl DomainLoginAttemptRepo for LoginAttemptRepo {
+    fn find_by_email(&self, email: &str) -> Result<Option<DomainLoginAttempts>, Error> {
+        let lock = self.file.lock_shared()?;
+        let store = self.file.load(&lock)?;
+
+        Ok(store
+            .login_attempts
+            .get(&DomainUser::email_key(email))
+            .cloned()
+            .map(DomainLoginAttempts::from))
+    }
+
+    fn save(&mut self, attempts: &DomainLoginAttempts) -> Result<(), Error> {
+        let lock = self.file.lock_exclusive()?;
+        let mut store = self.file.load(&lock)?;
+        store.login_attempts.insert(
+            attempts.email().clone(),
+            LoginAttempts::from(attempts.clone()),
+        );
+
+        self.file.store(&store)
+    }
+
+    fn delete(&mut self, email: &str) -> Result<(), Error> {
+        let lock = self.file.lock_exclusive()?;
+        let mut store = self.file.load(&lock)?;
+        store.login_attempts.remove(&DomainUser::email_key(email));
+
+        self.file.store(&store)
+    }
+}
+
 pub struct TaskRepo {
     file: File,
 }
 
 impl TaskRepo {
-    pub fn new(workspace: &str) -> Result<Self, Box<dyn error::Error>> {
+    pub fn new(workspace: &str) -> Result<Self, Error> {
         Ok(Self {
             file: File::new(workspace)?,
         })
@@ -73,31 +159,26 @@ impl TaskRepo {
 }
 
 impl DomainTaskRepo for TaskRepo {
-    fn next_id(&self) -> Result<String, Box<dyn error::Error>> {
+    fn next_id(&self) -> Result<String, Error> {
         Ok(rand::generate_string(70))
     }
 
-    fn get(&self, user_id: &str) -> Result<Vec<DomainTask>, Box<dyn error::Error>> {
-        let store = self.file.load()?;
+    fn query(&self, user_id: &str, query: &TaskQuery) -> Result<Vec<DomainTask>, Error> {
+        let lock = self.file.lock_shared()?;
+        let store = self.file.load(&lock)?;
 
-        let mut tasks = Vec::new();
-        for (_, task) in store.tasks {
-            if task.user_id != user_id {
-                continue;
-            }
-
-            tasks.push(DomainTask::from(task));
-        }
+        let tasks = store
+            .tasks
+            .into_values()
+            .filter(|task| task.user_id == user_id)
+            .map(DomainTask::from);
 
-        Ok(tasks)
+        Ok(query.apply(tasks))
     }
 
-    fn find_of_user(
-        &self,
-        id: &str,
-        user_id: &str,
-    ) -> Result<Option<DomainTask>, Box<dyn error::Error>> {
-        let store = self.file.load()?;
+    fn find_of_user(&self, id: &str, user_id: &str) -> Result<Option<DomainTask>, Error> {
+        let lock = self.file.lock_shared()?;
+        let store = self.file.load(&lock)?;
         for (_, task) in store.tasks {
             if task.id != id || task.user_id != user_id {
                 continue;
@@ -109,8 +190,9 @@ impl DomainTaskRepo for TaskRepo {
         Ok(None)
     }
 
-    fn save(&mut self, task: &DomainTask) -> Result<(), Box<dyn error::Error>> {
-        let mut store = self.file.load()?;
+    fn save(&mut self, task: &DomainTask) -> Result<(), Error> {
+        let lock = self.file.lock_exclusive()?;
+        let mut store = self.file.load(&lock)?;
         store
             .tasks
             .insert(task.id().clone(), Task::from(task.clone()));
@@ -118,15 +200,17 @@ impl DomainTaskRepo for TaskRepo {
         self.file.store(&store)
     }
 
-    fn delete(&mut self, id: &str) -> Result<(), Box<dyn error::Error>> {
-        let mut store = self.file.load()?;
+    fn delete(&mut self, id: &str) -> Result<(), Error> {
+        let lock = self.file.lock_exclusive()?;
+        let mut store = self.file.load(&lock)?;
         store.tasks.remove(id);
 
         self.file.store(&store)
     }
 
-    fn delete_of_user(&mut self, user_id: &str) -> Result<(), Box<dyn error::Error>> {
-        let mut store = self.file.load()?;
+    fn delete_of_user(&mut self, user_id: &str) -> Result<(), Error> {
+        let lock = self.file.lock_exclusive()?;
+        let mut store = self.file.load(&lock)?;
         let ids: Vec<String> = store
             .tasks
             .values()
@@ -143,37 +227,157 @@ impl DomainTaskRepo for TaskRepo {
 
 pub struct SessionManager {
     file: File,
+    tokens: TokenFile,
+    clock: Box<dyn DomainClock>,
+    policy: controller::SessionPolicy,
+    name: String,
 }
 
 impl SessionManager {
-    pub fn new(workspace: &str) -> Result<Self, Box<dyn error::Error>> {
+    pub fn new(
+        workspace: &str,
+        state_dir: &Path,
+        clock: Box<dyn DomainClock>,
+        policy: controller::SessionPolicy,
+    ) -> Result<Self, Error> {
         Ok(Self {
             file: File::new(workspace)?,
+            tokens: TokenFile::new(state_dir, workspace)?,
+            clock,
+            policy,
+            name: controller::DEFAULT_SESSION.to_string(),
         })
     }
 }
 
 impl controller::SessionManager for SessionManager {
-    fn push_authenticated_user_id(&mut self, user_id: &str) -> Result<(), Box<dyn error::Error>> {
-        let mut store = self.file.load()?;
-        store.session.authenticated_user_id = user_id.to_string();
+    fn select_session(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
 
-        self.file.store(&store)
+    fn push_authenticated_user_id(&mut self, user_id: &str) -> Result<(), Error> {
+        let lock = self.file.lock_exclusive()?;
+        let mut store = self.file.load(&lock)?;
+        let now = self.clock.now();
+        store
+            .session_records
+            .retain(|_, session| !controller::Session::from(session.clone()).is_expired(now));
+        if let Some(token) = self.tokens.get(&self.name)? {
+            store.session_records.remove(&state::hash_token(&token));
+        }
+
+        let token = rand::generate_string(40);
+        let session = controller::Session::new(
+            &self.name,
+            &state::hash_token(&token),
+            user_id,
+            now,
+            &self.policy,
+        );
+        store
+            .session_records
+            .insert(session.token_hash.clone(), Session::from(session));
+        self.file.store(&store)?;
+
+        self.tokens.set(&self.name, &token)
     }
 
-    fn pop_authenticated_user_id(&self) -> Result<Option<String>, Box<dyn error::Error>> {
-        let store = self.file.load()?;
-        let user_id = store.session.authenticated_user_id;
-        if user_id.is_empty() {
-            Ok(None)
-        } else {
-            Ok(Some(user_id))
+    fn pop_authenticated_user_id(&self) -> Result<Option<String>, Error> {
+        let token_hash = match self.tokens.get(&self.name)? {
+            Some(token) => state::hash_token(&token),
+            None => return Ok(None),
+        };
+
+        let session = {
+            let lock = self.file.lock_shared()?;
+            self.file.load(&lock)?.session_records.remove(&token_hash)
+        };
+        let mut session = match session {
+            Some(session) => controller::Session::from(session),
+            None => {
+                self.tokens.remove(&self.name)?;
+                return Ok(None);
+            }
+        };
+
+        let now = self.clock.now();
+        if session.is_expired(now) {
+            let lock = self.file.lock_exclusive()?;
+            let mut store = self.file.load(&lock)?;
+            store.session_records.remove(&token_hash);
+            self.file.store(&store)?;
+            self.tokens.remove(&self.name)?;
+            return Ok(None);
         }
+
+        if session.touch(now) {
+            let lock = self.file.lock_exclusive()?;
+            let mut store = self.file.load(&lock)?;
+            if let Some(record) = store.session_records.get_mut(&token_hash) {
+                record.last_used_at = now;
+                self.file.store(&store)?;
+            }
+        }
+
+        Ok(Some(session.user_id))
+    }
+
+    fn drop_authenticated_user_id(&mut self) -> Result<(), Error> {
+        let token = match self.tokens.get(&self.name)? {
+            Some(token) => token,
+            None => return Ok(()),
+        };
+
+        let lock = self.file.lock_exclusive()?;
+        let mut store = self.file.load(&lock)?;
+        store.session_records.remove(&state::hash_token(&token));
+        self.file.store(&store)?;
+
+        self.tokens.remove(&self.name)
+    }
+
+    fn find_sessions_of_user(&self, user_id: &str) -> Result<Vec<controller::Session>, Error> {
+        let lock = self.file.lock_shared()?;
+        let store = self.file.load(&lock)?;
+        let now = self.clock.now();
+        let mut sessions: Vec<controller::Session> = store
+            .session_records
+            .into_values()
+            .map(controller::Session::from)
+            .filter(|session| session.user_id == user_id && !session.is_expired(now))
+            .collect();
+        sessions.sort_by(|a, b| (&a.name, a.created_at).cmp(&(&b.name, b.created_at)));
+
+        Ok(sessions)
     }
 
-    fn drop_authenticated_user_id(&mut self) -> Result<(), Box<dyn error::Error>> {
-        let mut store = self.file.load()?;
-        store.session.authenticated_user_id = "".to_string();
+    fn drop_sessions_of_user(&mut self, user_id: &str) -> Result<(), Error> {
+        self.drop_session_records_of_user(user_id)?;
+
+        self.tokens.remove(&self.name)
+    }
+
+    fn drop_session_records_of_user(&mut self, user_id: &str) -> Result<(), Error> {
+        let lock = self.file.lock_exclusive()?;
+        let mut store = self.file.load(&lock)?;
+        store
+            .session_records
+            .retain(|_, session| session.user_id != user_id);
+
+        self.file.store(&store)
+    }
+
+    fn drop_other_sessions_of_user(&mut self, user_id: &str) -> Result<(), Error> {
+        let current = self
+            .tokens
+            .get(&self.name)?
+            .map(|token| state::hash_token(&token));
+
+        let lock = self.file.lock_exclusive()?;
+        let mut store = self.file.load(&lock)?;
+        store.session_records.retain(|token_hash, session| {
+            session.user_id != user_id || Some(token_hash) == current.as_ref()
+        });
 
         self.file.store(&store)
     }
@@ -181,70 +385,169 @@ impl controller::SessionManager for SessionManager {
 
 struct File {
     workspace: String,
+    cache: RefCell<Option<(Stamp, Store)>>,
 }
 
 impl File {
-    fn new(workspace: &str) -> Result<Self, Box<dyn error::Error>> {
+    fn new(workspace: &str) -> Result<Self, Error> {
         let file = Self {
             workspace: workspace.to_string(),
+            cache: RefCell::new(None),
         };
 
+        let _lock = file.lock_exclusive()?;
         file.init_store_file_if_not_exist()?;
 
         Ok(file)
     }
 
-    fn init_store_file_if_not_exist(&self) -> Result<(), Box<dyn error::Error>> {
-        let path = self.store_path()?;
+    fn lock_exclusive(&self) -> Result<Lock, Error> {
+        let lock = Lock::open(&self.lock_path(), true)?;
+        lock.file.lock_exclusive()?;
 
-        if Path::new(&path).exists() {
+        Ok(lock)
+    }
+
+    fn lock_shared(&self) -> Result<Lock, Error> {
+        let lock = Lock::open(&self.lock_path(), false)?;
+        lock.file.lock_shared()?;
+
+        Ok(lock)
+    }
+
+    fn init_store_file_if_not_exist(&self) -> Result<(), Error> {
+        if self.store_path().exists() {
             return Ok(());
         }
 
-        let store = serde_json::to_string(&Store::new())?;
-        fs::File::create(path)?.write_all(store.as_bytes())?;
-
-        Ok(())
+        self.write(&Store::new())
     }
 
-    fn load(&self) -> Result<Store, Box<dyn error::Error>> {
-        let path = self.store_path()?;
+    // Long-running commands such as the shell load the store many times, so reads parse it again
+    // only once another write has replaced the file. The stamp can miss a write that reused the
+    // inode within the mtime granularity, so a store about to be written back is always read anew.
+    fn load(&self, lock: &Lock) -> Result<Store, Error> {
         let mut store = String::new();
 
-        self.init_store_file_if_not_exist()?;
-        fs::File::open(path)
-            .map_err(|err| err.to_string())?
-            .read_to_string(&mut store)
-            .map_err(|err| err.to_string())?;
+        let mut file = fs::File::open(self.store_path())?;
+        let metadata = file.metadata()?;
+        let stamp = Stamp::new(&metadata);
+        if !lock.exclusive {
+            if let Some((cached, store)) = &*self.cache.borrow() {
+                if *cached == stamp {
+                    return Ok(store.clone());
+                }
+            }
+        }
+        let modified: DateTime<Utc> = metadata.modified()?.into();
+        file.read_to_string(&mut store)?;
+
+        let mut store: Store = serde_json::from_str(&store)?;
+        // Tasks written before created_at existed take the time the store was last written, which
+        // stays put until the next write persists it.
+        for task in store.tasks.values_mut() {
+            task.created_at.get_or_insert(modified);
+        }
+        *self.cache.borrow_mut() = Some((stamp, store.clone()));
+
+        Ok(store)
+    }
+
+    fn store(&self, store: &Store) -> Result<(), Error> {
+        self.write(store)?;
+        let stamp = Stamp::new(&fs::metadata(self.store_path())?);
+        *self.cache.borrow_mut() = Some((stamp, store.clone()));
 
-        Ok(serde_json::from_str(&store).map_err(|err| err.to_string())?)
+        Ok(())
     }
 
-    fn store(&self, store: &Store) -> Result<(), Box<dyn error::Error>> {
-        let path = self.store_path()?;
-        let store = serde_json::to_string(store).map_err(|err| err.to_string())?;
+    fn write(&self, store: &Store) -> Result<(), Error> {
+        let (path, tmp_path) = (self.store_path(), self.tmp_store_path());
+        let store = serde_json::to_string(store)?;
+
+        let mut tmp = fs::File::create(&tmp_path)?;
+        tmp.write_all(store.as_bytes())?;
+        tmp.sync_all()?;
+        fs::rename(&tmp_path, &path)?;
 
-        self.init_store_file_if_not_exist()?;
-        fs::File::create(path)?.write_all(store.as_bytes())?;
+        // The rename itself is durable only once the directory holding it is.
+        #[cfg(unix)]
+        fs::File::open(&self.workspace)?.sync_all()?;
 
         Ok(())
     }
 
-    fn store_path(&self) -> Result<String, Box<dyn error::Error>> {
-        Ok(Path::new(&self.workspace)
-            .join("store.json")
-            .to_str()
-            .ok_or("failed to locate store file")
-            .unwrap()
-            .to_string())
+    fn store_path(&self) -> PathBuf {
+        Path::new(&self.workspace).join("store.json")
+    }
+
+    fn tmp_store_path(&self) -> PathBuf {
+        Path::new(&self.workspace).join("store.json.tmp")
+    }
+
+    fn lock_path(&self) -> PathBuf {
+        Path::new(&self.workspace).join("store.json.lock")
+    }
+}
+
+#[derive(PartialEq)]
+struct Stamp {
+    modified: SystemTime,
+    len: u64,
+    inode: u64,
+}
+
+impl Stamp {
+    fn new(metadata: &fs::Metadata) -> Self {
+        Self {
+            modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            len: metadata.len(),
+            #[cfg(unix)]
+            inode: metadata.ino(),
+            #[cfg(not(unix))]
+            inode: 0,
+        }
+    }
+}
+
+struct Lock {
+    file: fs::File,
+    exclusive: bool,
+}
+
+impl Lock {
+    fn open(path: &Path, exclusive: bool) -> Result<Self, Error> {
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        Ok(Self { file, exclusive })
     }
 }
 
-#[derive(Serialize, Deserialize)]
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Storage(err.to_string())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 struct Store {
     users: HashMap<String, User>,
     tasks: HashMap<String, Task>,
-    session: Session,
+    #[serde(default)]
+    session_records: HashMap<String, Session>,
+    #[serde(default)]
+    login_attempts: HashMap<String, LoginAttempts>,
 }
 
 impl Store {
@@ -252,7 +555,8 @@ impl Store {
         Self {
             users: HashMap::new(),
             tasks: HashMap::new(),
-            session: Session::new(),
+            session_records: HashMap::new(),
+            login_attempts: HashMap::new(),
         }
     }
 }
@@ -262,6 +566,8 @@ struct User {
     id: String,
     email: String,
     password: String,
+    #[serde(default)]
+    created_at: DateTime<Utc>,
 }
 
 impl From<User> for DomainUser {
@@ -270,6 +576,7 @@ impl From<User> for DomainUser {
             id: user.id,
             email: user.email,
             password: Hash::from(user.password),
+            created_at: user.created_at,
         }
     }
 }
@@ -280,6 +587,34 @@ impl From<DomainUser> for User {
             id: user.id().clone(),
             email: user.email().clone(),
             password: user.password().clone().0,
+            created_at: user.created_at(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct LoginAttempts {
+    email: String,
+    failures: u32,
+    blocked_until: DateTime<Utc>,
+}
+
+impl From<LoginAttempts> for DomainLoginAttempts {
+    fn from(attempts: LoginAttempts) -> Self {
+        DomainLoginAttempts {
+            email: attempts.email,
+            failures: attempts.failures,
+            blocked_until: attempts.blocked_until,
+        }
+    }
+}
+
+impl From<DomainLoginAttempts> for LoginAttempts {
+    fn from(attempts: DomainLoginAttempts) -> Self {
+        LoginAttempts {
+            email: attempts.email().clone(),
+            failures: attempts.failures(),
+            blocked_until: attempts.blocked_until(),
         }
     }
 }
@@ -290,6 +625,20 @@ struct Task {
     user_id: String,
     name: String,
     completed: bool,
+    #[serde(default)]
+    due: Option<DateTime<Utc>>,
+    #[serde(default)]
+    priority: String,
+    #[serde(default)]
+    tags: BTreeSet<String>,
+    #[serde(default)]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    completed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    position: i64,
 }
 
 impl From<Task> for DomainTask {
@@ -299,6 +648,13 @@ impl From<Task> for DomainTask {
             user_id: task.user_id,
             name: task.name,
             completed: task.completed,
+            due: task.due,
+            priority: Priority::new(&task.priority).unwrap_or_default(),
+            tags: task.tags,
+            created_at: task.created_at.unwrap_or_default(),
+            updated_at: task.updated_at.or(task.created_at).unwrap_or_default(),
+            completed_at: task.completed_at,
+            position: task.position,
         }
     }
 }
@@ -310,19 +666,52 @@ impl From<DomainTask> for Task {
             user_id: task.user_id().clone(),
             name: task.name().clone(),
             completed: task.is_completed(),
+            due: task.due(),
+            priority: task.priority().name().to_string(),
+            tags: task.tags().clone(),
+            created_at: Some(task.created_at()),
+            updated_at: Some(task.updated_at()),
+            completed_at: task.completed_at(),
+            position: task.position(),
         }
     }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 struct Session {
-    authenticated_user_id: String,
+    name: String,
+    token_hash: String,
+    user_id: String,
+    created_at: DateTime<Utc>,
+    last_used_at: DateTime<Utc>,
+    idle_timeout: i64,
+    expires_at: DateTime<Utc>,
 }
 
-impl Session {
-    fn new() -> Self {
-        Self {
-            authenticated_user_id: String::new(),
+impl From<Session> for controller::Session {
+    fn from(session: Session) -> Self {
+        controller::Session {
+            name: session.name,
+            token_hash: session.token_hash,
+            user_id: session.user_id,
+            created_at: session.created_at,
+            last_used_at: session.last_used_at,
+            idle_timeout: Duration::seconds(session.idle_timeout),
+            expires_at: session.expires_at,
+        }
+    }
+}
+
+impl From<controller::Session> for Session {
+    fn from(session: controller::Session) -> Self {
+        Session {
+            name: session.name,
+            token_hash: session.token_hash,
+            user_id: session.user_id,
+            created_at: session.created_at,
+            last_used_at: session.last_used_at,
+            idle_timeout: session.idle_timeout.num_seconds(),
+            expires_at: session.expires_at,
         }
     }
 }
@@ -1,80 +1,126 @@
+use super::super::Clock as DomainClock;
+use super::super::LoginAttemptRepo as DomainLoginAttemptRepo;
 use super::super::TaskRepo as DomainTaskRepo;
 use super::super::UserRepo as DomainUserRepo;
-use super::super::{Task, User};
+use super::super::{Error, LoginAttempts, Task, TaskQuery, User};
 use super::rand;
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
-use std::error;
 
+#[derive(Default)]
 pub struct UserRepo {
     users: HashMap<String, User>,
 }
 
 impl UserRepo {
     pub fn new() -> Self {
-        Self {
-            users: HashMap::new(),
-        }
+        Self::default()
     }
 }
 
 impl DomainUserRepo for UserRepo {
-    fn next_id(&self) -> Result<String, Box<dyn error::Error>> {
+    fn next_id(&self) -> Result<String, Error> {
         Ok(rand::generate_string(50))
     }
 
-    fn find_by_email(&self, email: &str) -> Result<Option<User>, Box<dyn error::Error>> {
-        for (_, user) in &self.users {
-            if user.email() == email {
-                return Ok(Some(user.clone()));
-            }
-        }
+    fn find_by_id(&self, id: &str) -> Result<Option<User>, Error> {
+        Ok(self.users.get(id).cloned())
+    }
 
-        Ok(None)
+    fn find_by_email(&self, email: &str) -> Result<Option<User>, Error> {
+        let email = User::email_key(email);
+        let user = self
+            .users
+            .values()
+            .filter(|user| User::email_key(user.email()) == email)
+            .min_by_key(|user| (user.created_at(), user.id().clone()));
+
+        Ok(user.cloned())
     }
 
-    fn save(&mut self, user: &User) -> Result<(), Box<dyn error::Error>> {
+    fn find_all(&self) -> Result<Vec<User>, Error> {
+        let mut users: Vec<User> = self.users.values().cloned().collect();
+        users.sort_by_key(|user| (user.created_at(), user.id().clone()));
+
+        Ok(users)
+    }
+
+    fn save(&mut self, user: &User) -> Result<(), Error> {
+        let email = User::email_key(user.email());
+        if self
+            .users
+            .values()
+            .any(|other| other.id() != user.id() && User::email_key(other.email()) == email)
+        {
+            return Err(Error::email_taken());
+        }
+
         self.users.insert(user.id().clone(), user.clone());
         Ok(())
     }
 
-    fn delete(&mut self, id: &str) -> Result<(), Box<dyn error::Error>> {
+    fn delete(&mut self, id: &str) -> Result<(), Error> {
         self.users.remove(id);
         Ok(())
     }
 }
 
+#[derive(Default)]
+pub struct LoginAttemptRepo {
+    attempts: HashMap<String, LoginAttempts>,
+}
+
+impl LoginAttemptRepo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl DomainLoginAttemptRepo for LoginAttemptRepo {
+    fn find_by_email(&self, email: &str) -> Result<Option<LoginAttempts>, Error> {
+        Ok(self.attempts.get(&User::email_key(email)).cloned())
+    }
+
+    fn save(&mut self, attempts: &LoginAttempts) -> Result<(), Error> {
+        self.attempts
+            .insert(attempts.email().clone(), attempts.clone());
+        Ok(())
+    }
+
+    fn delete(&mut self, email: &str) -> Result<(), Error> {
+        self.attempts.remove(&User::email_key(email));
+        Ok(())
+    }
+}
+
+#[derive(Default)]
 pub struct TaskRepo {
     tasks: HashMap<String, Task>,
 }
 
 impl TaskRepo {
     pub fn new() -> Self {
-        Self {
-            tasks: HashMap::new(),
-        }
+        Self::default()
     }
 }
 
 impl DomainTaskRepo for TaskRepo {
-    fn next_id(&self) -> Result<String, Box<dyn error::Error>> {
+    fn next_id(&self) -> Result<String, Error> {
         Ok(rand::generate_string(70))
     }
 
-    fn get(&self, user_id: &str) -> Result<Vec<Task>, Box<dyn error::Error>> {
-        let mut tasks = Vec::new();
-        for (_, task) in &self.tasks {
-            if task.user_id() != user_id {
-                continue;
-            }
-
-            tasks.push(task.clone());
-        }
+    fn query(&self, user_id: &str, query: &TaskQuery) -> Result<Vec<Task>, Error> {
+        let tasks = self
+            .tasks
+            .values()
+            .filter(|task| task.user_id() == user_id)
+            .cloned();
 
-        Ok(tasks)
+        Ok(query.apply(tasks))
     }
 
-    fn find_of_user(&self, id: &str, user_id: &str) -> Result<Option<Task>, Box<dyn error::Error>> {
-        for (_, task) in &self.tasks {
+    fn find_of_user(&self, id: &str, user_id: &str) -> Result<Option<Task>, Error> {
+        for task in self.tasks.values() {
             if task.id() != id || task.user_id() != user_id {
                 continue;
             }
@@ -85,17 +131,17 @@ impl DomainTaskRepo for TaskRepo {
         Ok(None)
     }
 
-    fn save(&mut self, task: &Task) -> Result<(), Box<dyn error::Error>> {
+    fn save(&mut self, task: &Task) -> Result<(), Error> {
         self.tasks.insert(task.id().clone(), task.clone());
         Ok(())
     }
 
-    fn delete(&mut self, id: &str) -> Result<(), Box<dyn error::Error>> {
+    fn delete(&mut self, id: &str) -> Result<(), Error> {
         self.tasks.remove(id);
         Ok(())
     }
 
-    fn delete_of_user(&mut self, user_id: &str) -> Result<(), Box<dyn error::Error>> {
+    fn delete_of_user(&mut self, user_id: &str) -> Result<(), Error> {
         let ids: Vec<String> = self
             .tasks
             .values()
@@ -109,3 +155,19 @@ impl DomainTaskRepo for TaskRepo {
         Ok(())
     }
 }
+
+pub struct Clock {
+    now: DateTime<Utc>,
+}
+
+impl Clock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self { now }
+    }
+}
+
+impl DomainClock for Clock {
+    fn now(&self) -> DateTime<Utc> {
+        self.now
+    }
+}
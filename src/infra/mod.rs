@@ -2,4 +2,10 @@ pub mod memory;
 
 pub mod file;
 
+pub mod sqlite;
+
+pub mod system;
+
 mod rand;
+
+pub mod state;
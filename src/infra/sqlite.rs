@@ -0,0 +1,696 @@
+extern crate rusqlite;
+
+use super::super::gateway::controller;
+use super::super::Clock as DomainClock;
+use super::super::LoginAttemptRepo as DomainLoginAttemptRepo;
+use super::super::Task as DomainTask;
+use super::super::TaskRepo as DomainTaskRepo;
+use super::super::User as DomainUser;
+use super::super::UserRepo as DomainUserRepo;
+use super::super::{
+    Error, Hash, LoginAttempts, Priority, SortDirection, TagFilter, TaskQuery, TaskSort,
+    TaskSortKey,
+};
+use super::rand;
+use super::state::{self, TokenFile};
+use chrono::Duration;
+use rusqlite::{params, Connection, OptionalExtension, Row, ToSql, TransactionBehavior};
+use std::path::Path;
+
+pub struct UserRepo {
+    db: Db,
+}
+
+impl UserRepo {
+    pub fn new(workspace: &str) -> Result<Self, Error> {
+        Ok(Self {
+            db: Db::new(workspace)?,
+        })
+    }
+
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Ok(Self {
+            db: Db::open_in_memory()?,
+        })
+    }
+}
+
+impl DomainUserRepo for UserRepo {
+    fn next_id(&self) -> Result<String, Error> {
+        Ok(rand::generate_string(50))
+    }
+
+    fn find_by_id(&self, id: &str) -> Result<Option<DomainUser>, Error> {
+        let user = self
+            .db
+            .conn
+            .query_row(
+                "SELECT id, email, password, created_at FROM users WHERE id = ?1",
+                params![id],
+                user_from_row,
+            )
+            .optional()?;
+
+        Ok(user)
+    }
+
+    fn find_by_email(&self, email: &str) -> Result<Option<DomainUser>, Error> {
+        let user = self
+            .db
+            .conn
+            .query_row(
+                "SELECT id, email, password, created_at FROM users
+                WHERE email_key = ?1 ORDER BY created_at, id LIMIT 1",
+                params![DomainUser::email_key(email)],
+                user_from_row,
+            )
+            .optional()?;
+
+        Ok(user)
+    }
+
+    fn find_all(&self) -> Result<Vec<DomainUser>, Error> {
+        let mut stmt = self
+            .db
+            .conn
+            .prepare("SELECT id, email, password, created_at FROM users ORDER BY created_at, id")?;
+        let users = stmt
+            .query_map(params![], user_from_row)?
+            .collect::<Result<Vec<DomainUser>, rusqlite::Error>>()?;
+
+        Ok(users)
+    }
+
+    fn save(&mut self, user: &DomainUser) -> Result<(), Error> {
+        let changed = match self.db.conn.execute(
+            "INSERT INTO users (id, email, email_key, password, created_at)
+            SELECT ?1, ?2, ?3, ?4, ?5 WHERE NOT EXISTS (
+                SELECT 1 FROM users WHERE email_key = ?3 AND id != ?1
+            )
+            ON CONFLICT (id) DO UPDATE SET
+                email = excluded.email, email_key = excluded.email_key,
+                password = excluded.password, created_at = excluded.created_at",
+            params![
+                user.id(),
+                user.email(),
+                DomainUser::email_key(user.email()),
+                user.password().0,
+                user.created_at()
+            ],
+        ) {
+            Err(rusqlite::Error::SqliteFailure(err, _))
+                if err.code == rusqlite::ErrorCode::ConstraintViolation =>
+            {
+                0
+            }
+            changed => changed?,
+        };
+        if changed == 0 {
+            return Err(Error::email_taken());
+        }
+
+        Ok(())
+    }
+
+    fn delete(&mut self, id: &str) -> Result<(), Error> {
+        self.db
+            .conn
+            .execute("DELETE FROM users WHERE id = ?1", params![id])?;
+
+        Ok(())
+    }
+}
+
+pub struct LoginAttemptRepo {
+    db: Db,
+}
+
+impl LoginAttemptRepo {
+    pub fn new(workspace: &str) -> Result<Self, Error> {
+        Ok(Self {
+            db: Db::new(workspace)?,
+        })
+    }
+
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Ok(Self {
+            db: Db::open_in_memory()?,
+        })
+    }
+}
+
+impl DomainLoginAttemptRepo for LoginAttemptRepo {
+    fn find_by_email(&self, email: &str) -> Result<Option<LoginAttempts>, Error> {
+        let attempts = self
+            .db
+            .conn
+            .query_row(
+                "SELECT email, failures, blocked_until FROM login_attempts WHERE email = ?1",
+                params![DomainUser::email_key(email)],
+                |row| {
+                    Ok(LoginAttempts {
+                        email: row.get(0)?,
+                        failures: row.get(1)?,
+                        blocked_until: row.get(2)?,
+                    })
+                },
+            )
+            .optional()?;
+
+        Ok(attempts)
+    }
+
+    fn save(&mut self, attempts: &LoginAttempts) -> Result<(), Error> {
+        self.db.conn.execute(
+            "INSERT OR REPLACE INTO login_attempts (email, failures, blocked_until)
+            VALUES (?1, ?2, ?3)",
+            params![
+                attempts.email(),
+                attempts.failures(),
+                attempts.blocked_until()
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    fn delete(&mut self, email: &str) -> Result<(), Error> {
+        self.db.conn.execute(
+            "DELETE FROM login_attempts WHERE email = ?1",
+            params![DomainUser::email_key(email)],
+        )?;
+
+        Ok(())
+    }
+}
+
+pub struct TaskRepo {
+    db: Db,
+}
+
+impl TaskRepo {
+    pub fn new(workspace: &str) -> Result<Self, Error> {
+        Ok(Self {
+            db: Db::new(workspace)?,
+        })
+    }
+
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Ok(Self {
+            db: Db::open_in_memory()?,
+        })
+    }
+}
+
+impl DomainTaskRepo for TaskRepo {
+    fn next_id(&self) -> Result<String, Error> {
+        Ok(rand::generate_string(70))
+    }
+
+    fn query(&self, user_id: &str, query: &TaskQuery) -> Result<Vec<DomainTask>, Error> {
+        let mut conds = vec!["user_id = ?".to_string()];
+        let mut params: Vec<Box<dyn ToSql>> = vec![Box::new(user_id.to_string())];
+        if let Some(completed) = query.completed {
+            conds.push("completed = ?".to_string());
+            params.push(Box::new(completed));
+        }
+        if let Some(name) = &query.name_contains {
+            conds.push("instr(name_key, ?) > 0".to_string());
+            params.push(Box::new(DomainTask::name_key(name)));
+        }
+        if let Some(filter) = &query.tags {
+            let tags = filter.tags();
+            if !tags.is_empty() {
+                let having = match filter {
+                    TagFilter::All(_) => tags.len(),
+                    TagFilter::Any(_) => 1,
+                };
+                conds.push(format!(
+                    "id IN (
+                        SELECT task_id FROM task_tags WHERE tag IN ({})
+                        GROUP BY task_id HAVING count(*) >= {}
+                    )",
+                    vec!["?"; tags.len()].join(", "),
+                    having
+                ));
+                for tag in tags {
+                    params.push(Box::new(tag.clone()));
+                }
+            }
+        }
+        if let Some(before) = query.due_before {
+            conds.push("due < ?".to_string());
+            params.push(Box::new(before));
+        }
+        if let Some(after) = query.due_after {
+            conds.push("due >= ?".to_string());
+            params.push(Box::new(after));
+        }
+        params.push(Box::new(query.limit.map_or(-1, |limit| limit as i64)));
+        params.push(Box::new(query.offset as i64));
+
+        let mut stmt = self.db.conn.prepare(&format!(
+            "SELECT {} FROM tasks WHERE {} ORDER BY {} LIMIT ? OFFSET ?",
+            TASK_COLUMNS,
+            conds.join(" AND "),
+            order_by(&query.sort),
+        ))?;
+        let tasks = stmt
+            .query_map(params.iter().map(|param| param.as_ref()), task_from_row)?
+            .collect::<Result<Vec<DomainTask>, rusqlite::Error>>()?;
+
+        Ok(tasks)
+    }
+
+    fn find_of_user(&self, id: &str, user_id: &str) -> Result<Option<DomainTask>, Error> {
+        let task = self
+            .db
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM tasks WHERE id = ?1 AND user_id = ?2",
+                    TASK_COLUMNS
+                ),
+                params![id, user_id],
+                task_from_row,
+            )
+            .optional()?;
+
+        Ok(task)
+    }
+
+    fn save(&mut self, task: &DomainTask) -> Result<(), Error> {
+        let tx = self.db.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO tasks (
+                id, user_id, name, name_key, completed, due, priority, created_at, updated_at,
+                completed_at, position
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+            ON CONFLICT (id) DO UPDATE SET
+                user_id = excluded.user_id, name = excluded.name, name_key = excluded.name_key,
+                completed = excluded.completed, due = excluded.due, priority = excluded.priority,
+                created_at = excluded.created_at, updated_at = excluded.updated_at,
+                completed_at = excluded.completed_at, position = excluded.position",
+            params![
+                task.id(),
+                task.user_id(),
+                task.name(),
+                DomainTask::name_key(task.name()),
+                task.is_completed(),
+                task.due(),
+                task.priority().name(),
+                task.created_at(),
+                task.updated_at(),
+                task.completed_at(),
+                task.position()
+            ],
+        )?;
+        tx.execute(
+            "DELETE FROM task_tags WHERE task_id = ?1",
+            params![task.id()],
+        )?;
+        for tag in task.tags() {
+            tx.execute(
+                "INSERT INTO task_tags (task_id, tag) VALUES (?1, ?2)",
+                params![task.id(), tag],
+            )?;
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    fn delete(&mut self, id: &str) -> Result<(), Error> {
+        let tx = self.db.conn.transaction()?;
+        tx.execute("DELETE FROM task_tags WHERE task_id = ?1", params![id])?;
+        tx.execute("DELETE FROM tasks WHERE id = ?1", params![id])?;
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    fn delete_of_user(&mut self, user_id: &str) -> Result<(), Error> {
+        let tx = self.db.conn.transaction()?;
+        tx.execute(
+            "DELETE FROM task_tags WHERE task_id IN (SELECT id FROM tasks WHERE user_id = ?1)",
+            params![user_id],
+        )?;
+        tx.execute("DELETE FROM tasks WHERE user_id = ?1", params![user_id])?;
+        tx.commit()?;
+
+        Ok(())
+    }
+}
+
+pub struct SessionManager {
+    db: Db,
+    tokens: TokenFile,
+    clock: Box<dyn DomainClock>,
+    policy: controller::SessionPolicy,
+    name: String,
+}
+
+impl SessionManager {
+    pub fn new(
+        workspace: &str,
+        state_dir: &Path,
+        clock: Box<dyn DomainClock>,
+        policy: controller::SessionPolicy,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            db: Db::new(workspace)?,
+            tokens: TokenFile::new(state_dir, workspace)?,
+            clock,
+            policy,
+            name: controller::DEFAULT_SESSION.to_string(),
+        })
+    }
+
+    fn find_session(&self, token_hash: &str) -> Result<Option<controller::Session>, Error> {
+        let session = self
+            .db
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM session_records WHERE token_hash = ?1",
+                    SESSION_COLUMNS
+                ),
+                params![token_hash],
+                session_from_row,
+            )
+            .optional()?;
+
+        Ok(session)
+    }
+
+    fn delete_session(&self, token_hash: &str) -> Result<(), Error> {
+        self.db.conn.execute(
+            "DELETE FROM session_records WHERE token_hash = ?1",
+            params![token_hash],
+        )?;
+
+        Ok(())
+    }
+}
+
+impl controller::SessionManager for SessionManager {
+    fn select_session(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    fn push_authenticated_user_id(&mut self, user_id: &str) -> Result<(), Error> {
+        let now = self.clock.now();
+        let mut stmt = self
+            .db
+            .conn
+            .prepare(&format!("SELECT {} FROM session_records", SESSION_COLUMNS))?;
+        let sessions = stmt
+            .query_map(params![], session_from_row)?
+            .collect::<Result<Vec<controller::Session>, rusqlite::Error>>()?;
+        for session in sessions {
+            if session.is_expired(now) {
+                self.delete_session(&session.token_hash)?;
+            }
+        }
+        if let Some(token) = self.tokens.get(&self.name)? {
+            self.delete_session(&state::hash_token(&token))?;
+        }
+
+        let token = rand::generate_string(40);
+        let session = controller::Session::new(
+            &self.name,
+            &state::hash_token(&token),
+            user_id,
+            now,
+            &self.policy,
+        );
+        self.db.conn.execute(
+            &format!(
+                "INSERT INTO session_records ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                SESSION_COLUMNS
+            ),
+            params![
+                session.token_hash,
+                session.name,
+                session.user_id,
+                session.created_at,
+                session.last_used_at,
+                session.idle_timeout.num_seconds(),
+                session.expires_at
+            ],
+        )?;
+
+        self.tokens.set(&self.name, &token)
+    }
+
+    fn pop_authenticated_user_id(&self) -> Result<Option<String>, Error> {
+        let token_hash = match self.tokens.get(&self.name)? {
+            Some(token) => state::hash_token(&token),
+            None => return Ok(None),
+        };
+        let mut session = match self.find_session(&token_hash)? {
+            Some(session) => session,
+            None => {
+                self.tokens.remove(&self.name)?;
+                return Ok(None);
+            }
+        };
+
+        let now = self.clock.now();
+        if session.is_expired(now) {
+            self.delete_session(&token_hash)?;
+            self.tokens.remove(&self.name)?;
+            return Ok(None);
+        }
+
+        if session.touch(now) {
+            self.db.conn.execute(
+                "UPDATE session_records SET last_used_at = ?1 WHERE token_hash = ?2",
+                params![now, token_hash],
+            )?;
+        }
+
+        Ok(Some(session.user_id))
+    }
+
+    fn drop_authenticated_user_id(&mut self) -> Result<(), Error> {
+        if let Some(token) = self.tokens.get(&self.name)? {
+            self.delete_session(&state::hash_token(&token))?;
+            self.tokens.remove(&self.name)?;
+        }
+
+        Ok(())
+    }
+
+    fn find_sessions_of_user(&self, user_id: &str) -> Result<Vec<controller::Session>, Error> {
+        let now = self.clock.now();
+        let mut stmt = self.db.conn.prepare(&format!(
+            "SELECT {} FROM session_records WHERE user_id = ?1 ORDER BY name, created_at",
+            SESSION_COLUMNS
+        ))?;
+        let sessions = stmt
+            .query_map(params![user_id], session_from_row)?
+            .collect::<Result<Vec<controller::Session>, rusqlite::Error>>()?;
+
+        Ok(sessions
+            .into_iter()
+            .filter(|session| !session.is_expired(now))
+            .collect())
+    }
+
+    fn drop_sessions_of_user(&mut self, user_id: &str) -> Result<(), Error> {
+        self.drop_session_records_of_user(user_id)?;
+
+        self.tokens.remove(&self.name)
+    }
+
+    fn drop_session_records_of_user(&mut self, user_id: &str) -> Result<(), Error> {
+        self.db.conn.execute(
+            "DELETE FROM session_records WHERE user_id = ?1",
+            params![user_id],
+        )?;
+
+        Ok(())
+    }
+
+    fn drop_other_sessions_of_user(&mut self, user_id: &str) -> Result<(), Error> {
+        let current = self
+            .tokens
+            .get(&self.name)?
+            .map(|token| state::hash_token(&token));
+        self.db.conn.execute(
+            "DELETE FROM session_records WHERE user_id = ?1 AND token_hash IS NOT ?2",
+            params![user_id, current],
+        )?;
+
+        Ok(())
+    }
+}
+
+struct Db {
+    conn: Connection,
+}
+
+impl Db {
+    fn new(workspace: &str) -> Result<Self, Error> {
+        Self::open(Connection::open(
+            Path::new(workspace).join("store.sqlite3"),
+        )?)
+    }
+
+    fn open_in_memory() -> Result<Self, Error> {
+        Self::open(Connection::open_in_memory()?)
+    }
+
+    fn open(conn: Connection) -> Result<Self, Error> {
+        let mut db = Self { conn };
+
+        db.migrate()?;
+
+        Ok(db)
+    }
+
+    fn migrate(&mut self) -> Result<(), Error> {
+        loop {
+            let tx = self
+                .conn
+                .transaction_with_behavior(TransactionBehavior::Immediate)?;
+            let version: i64 = tx.query_row("PRAGMA user_version", params![], |row| row.get(0))?;
+            match MIGRATIONS.get(version as usize) {
+                Some(sql) => tx.execute_batch(sql)?,
+                None => return Ok(()),
+            }
+
+            tx.execute_batch(&format!("PRAGMA user_version = {};", version + 1))?;
+            tx.commit()?;
+        }
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        Error::Storage(err.to_string())
+    }
+}
+
+const MIGRATIONS: &[&str] = &["CREATE TABLE IF NOT EXISTS users (
+        id TEXT PRIMARY KEY NOT NULL,
+        email TEXT NOT NULL,
+        email_key TEXT NOT NULL,
+        password TEXT NOT NULL,
+        created_at TEXT NOT NULL
+    );
+    CREATE UNIQUE INDEX IF NOT EXISTS users_email_key ON users (email_key);
+
+    CREATE TABLE IF NOT EXISTS tasks (
+        id TEXT PRIMARY KEY NOT NULL,
+        user_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        name_key TEXT NOT NULL,
+        completed INTEGER NOT NULL,
+        due TEXT,
+        priority TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL,
+        completed_at TEXT,
+        position INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS tasks_user_id_position ON tasks (user_id, position);
+
+    CREATE TABLE IF NOT EXISTS task_tags (
+        task_id TEXT NOT NULL,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (task_id, tag)
+    );
+    CREATE INDEX IF NOT EXISTS task_tags_tag ON task_tags (tag);
+
+    CREATE TABLE IF NOT EXISTS session_records (
+        token_hash TEXT PRIMARY KEY NOT NULL,
+        name TEXT NOT NULL,
+        user_id TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        last_used_at TEXT NOT NULL,
+        idle_timeout INTEGER NOT NULL,
+        expires_at TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS session_records_user_id ON session_records (user_id);
+
+    CREATE TABLE IF NOT EXISTS login_attempts (
+        email TEXT PRIMARY KEY NOT NULL,
+        failures INTEGER NOT NULL,
+        blocked_until TEXT NOT NULL
+    );"];
+
+const TASK_COLUMNS: &str = "id, user_id, name, completed, due, priority,
+    (SELECT group_concat(tag, ' ') FROM task_tags WHERE task_id = tasks.id),
+    created_at, updated_at, completed_at, position";
+
+const SESSION_COLUMNS: &str =
+    "token_hash, name, user_id, created_at, last_used_at, idle_timeout, expires_at";
+
+fn order_by(sort: &TaskSort) -> String {
+    let direction = match sort.direction {
+        SortDirection::Asc => "ASC",
+        SortDirection::Desc => "DESC",
+    };
+    let key = match sort.key {
+        TaskSortKey::Priority => {
+            let whens: Vec<String> = Priority::NAMES
+                .iter()
+                .enumerate()
+                .map(|(i, name)| format!("WHEN '{}' THEN {}", name, i))
+                .collect();
+            format!("CASE priority {} END {}", whens.join(" "), direction)
+        }
+        TaskSortKey::Due => format!("due IS NULL, due {}", direction),
+        TaskSortKey::Name => format!("name_key {}", direction),
+        TaskSortKey::Position => format!("position {}", direction),
+        TaskSortKey::Created => format!("created_at {}", direction),
+        TaskSortKey::Updated => format!("updated_at {}", direction),
+    };
+
+    format!("{}, position, created_at, id", key)
+}
+
+fn user_from_row(row: &Row) -> Result<DomainUser, rusqlite::Error> {
+    Ok(DomainUser {
+        id: row.get(0)?,
+        email: row.get(1)?,
+        password: Hash::from(row.get::<_, String>(2)?),
+        created_at: row.get(3)?,
+    })
+}
+
+fn session_from_row(row: &Row) -> Result<controller::Session, rusqlite::Error> {
+    Ok(controller::Session {
+        token_hash: row.get(0)?,
+        name: row.get(1)?,
+        user_id: row.get(2)?,
+        created_at: row.get(3)?,
+        last_used_at: row.get(4)?,
+        idle_timeout: Duration::seconds(row.get(5)?),
+        expires_at: row.get(6)?,
+    })
+}
+
+fn task_from_row(row: &Row) -> Result<DomainTask, rusqlite::Error> {
+    Ok(DomainTask {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        name: row.get(2)?,
+        completed: row.get(3)?,
+        due: row.get(4)?,
+        priority: Priority::new(&row.get::<_, String>(5)?).unwrap_or_default(),
+        tags: row
+            .get::<_, Option<String>>(6)?
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(String::from)
+            .collect(),
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+        completed_at: row.get(9)?,
+        position: row.get(10)?,
+    })
+}
@@ -0,0 +1,162 @@
+extern crate fs2;
+extern crate serde_json;
+extern crate sha2;
+
+use super::super::Error;
+use fs2::FileExt;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::prelude::*;
+#[cfg(unix)]
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+type Tokens = HashMap<String, HashMap<String, String>>;
+
+pub struct TokenFile {
+    dir: PathBuf,
+    workspace: String,
+}
+
+impl TokenFile {
+    pub fn new(dir: &Path, workspace: &str) -> Result<Self, Error> {
+        let workspace = fs::canonicalize(workspace)?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            workspace: workspace.to_string_lossy().to_string(),
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<String>, Error> {
+        let _lock = self.lock()?;
+        let tokens = self.load()?;
+
+        Ok(tokens
+            .get(&self.workspace)
+            .and_then(|tokens| tokens.get(name))
+            .cloned())
+    }
+
+    pub fn set(&self, name: &str, token: &str) -> Result<(), Error> {
+        let _lock = self.lock()?;
+        let mut tokens = self.load()?;
+        tokens
+            .entry(self.workspace.clone())
+            .or_default()
+            .insert(name.to_string(), token.to_string());
+
+        self.store(&tokens)
+    }
+
+    pub fn remove(&self, name: &str) -> Result<(), Error> {
+        let _lock = self.lock()?;
+        let mut tokens = self.load()?;
+        if let Some(workspace) = tokens.get_mut(&self.workspace) {
+            workspace.remove(name);
+            if workspace.is_empty() {
+                tokens.remove(&self.workspace);
+            }
+        }
+
+        self.store(&tokens)
+    }
+
+    fn lock(&self) -> Result<Lock, Error> {
+        create_private_dir(&self.dir)?;
+        let file = open_private(&self.dir.join("sessions.json.lock"), false)?;
+        file.lock_exclusive()?;
+
+        Ok(Lock { file })
+    }
+
+    fn load(&self) -> Result<Tokens, Error> {
+        let path = self.path();
+        if !path.exists() {
+            return Ok(Tokens::new());
+        }
+
+        let mut tokens = String::new();
+        fs::File::open(path)?.read_to_string(&mut tokens)?;
+
+        Ok(serde_json::from_str(&tokens)?)
+    }
+
+    fn store(&self, tokens: &Tokens) -> Result<(), Error> {
+        let (path, tmp_path) = (self.path(), self.dir.join("sessions.json.tmp"));
+        let tokens = serde_json::to_string(tokens)?;
+
+        let mut tmp = open_private(&tmp_path, true)?;
+        tmp.write_all(tokens.as_bytes())?;
+        tmp.sync_all()?;
+        fs::rename(&tmp_path, &path)?;
+
+        Ok(())
+    }
+
+    fn path(&self) -> PathBuf {
+        self.dir.join("sessions.json")
+    }
+}
+
+struct Lock {
+    file: fs::File,
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+pub fn history_path(dir: &Path) -> Result<PathBuf, Error> {
+    create_private_dir(dir)?;
+
+    Ok(dir.join("history"))
+}
+
+pub fn state_dir() -> Result<PathBuf, Error> {
+    if let Some(dir) = env::var_os("XDG_STATE_HOME").map(PathBuf::from) {
+        if dir.is_absolute() {
+            return Ok(dir.join("todo"));
+        }
+    }
+
+    match env::var_os("HOME") {
+        Some(home) => Ok(Path::new(&home).join(".local").join("state").join("todo")),
+        None => Err(Error::Internal(String::from(
+            "failed to locate state directory: neither XDG_STATE_HOME nor HOME is set",
+        ))),
+    }
+}
+
+fn create_private_dir(dir: &Path) -> Result<(), Error> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    builder.mode(0o700);
+
+    Ok(builder.create(dir)?)
+}
+
+fn open_private(path: &Path, truncate: bool) -> Result<fs::File, Error> {
+    let mut options = fs::OpenOptions::new();
+    options
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(truncate);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let file = options.open(path)?;
+    #[cfg(unix)]
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+
+    Ok(file)
+}
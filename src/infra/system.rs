@@ -0,0 +1,10 @@
+use super::super::Clock as DomainClock;
+use chrono::{DateTime, Utc};
+
+pub struct Clock {}
+
+impl DomainClock for Clock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
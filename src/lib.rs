@@ -2,14 +2,106 @@ pub mod gateway;
 pub mod infra;
 pub mod usecase;
 
-use bcrypt;
+use chrono::{DateTime, Duration, Utc};
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
 use std::error;
+use std::fmt;
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    Validation(String),
+    NotFound(String),
+    Unauthorized(String),
+    Conflict(String),
+    TooManyAttempts(String, Duration),
+    Storage(String),
+    Internal(String),
+}
+
+impl Error {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Validation(_) => "validation",
+            Error::NotFound(_) => "not_found",
+            Error::Unauthorized(_) => "unauthorized",
+            Error::Conflict(_) => "conflict",
+            Error::TooManyAttempts(..) => "too_many_attempts",
+            Error::Storage(_) => "storage",
+            Error::Internal(_) => "internal",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Error::Validation(msg)
+            | Error::NotFound(msg)
+            | Error::Unauthorized(msg)
+            | Error::Conflict(msg)
+            | Error::TooManyAttempts(msg, _)
+            | Error::Storage(msg)
+            | Error::Internal(msg) => msg,
+        }
+    }
+
+    pub fn context(self, context: &str) -> Self {
+        let msg = format!("{}: {}", context, self.message());
+        match self {
+            Error::Validation(_) => Error::Validation(msg),
+            Error::NotFound(_) => Error::NotFound(msg),
+            Error::Unauthorized(_) => Error::Unauthorized(msg),
+            Error::Conflict(_) => Error::Conflict(msg),
+            Error::TooManyAttempts(_, retry_after) => Error::TooManyAttempts(msg, retry_after),
+            Error::Storage(_) => Error::Storage(msg),
+            Error::Internal(_) => Error::Internal(msg),
+        }
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::TooManyAttempts(_, retry_after) => Some(*retry_after),
+            _ => None,
+        }
+    }
+
+    pub fn email_taken() -> Self {
+        Error::Conflict(String::from("email is already taken"))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Storage(err.to_string())
+    }
+}
+
+impl From<bcrypt::BcryptError> for Error {
+    fn from(err: bcrypt::BcryptError) -> Self {
+        Error::Internal(err.to_string())
+    }
+}
+
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+}
 
 pub trait UserRepo {
-    fn next_id(&self) -> Result<String, Box<dyn error::Error>>;
-    fn find_by_email(&self, email: &str) -> Result<Option<User>, Box<dyn error::Error>>;
-    fn save(&mut self, user: &User) -> Result<(), Box<dyn error::Error>>;
-    fn delete(&mut self, id: &str) -> Result<(), Box<dyn error::Error>>;
+    fn next_id(&self) -> Result<String, Error>;
+    fn find_by_id(&self, id: &str) -> Result<Option<User>, Error>;
+    fn find_by_email(&self, email: &str) -> Result<Option<User>, Error>;
+    fn find_all(&self) -> Result<Vec<User>, Error>;
+    fn save(&mut self, user: &User) -> Result<(), Error>;
+    fn delete(&mut self, id: &str) -> Result<(), Error>;
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -17,17 +109,25 @@ pub struct User {
     id: String,
     email: String,
     password: Hash,
+    created_at: DateTime<Utc>,
 }
 
 impl User {
-    pub fn new(id: &str, email: &str, password: &Hash) -> Result<Self, Box<dyn error::Error>> {
+    pub fn new(
+        id: &str,
+        email: &str,
+        password: &Hash,
+        created_at: DateTime<Utc>,
+    ) -> Result<Self, Error> {
+        let email = Self::normalize_email(email);
         Self::verify_id(id)?;
-        Self::verify_email(email)?;
+        Self::verify_email(&email)?;
 
         Ok(Self {
             id: id.to_string(),
-            email: email.to_string(),
+            email,
             password: password.clone(),
+            created_at,
         })
     }
 
@@ -43,18 +143,73 @@ impl User {
         &self.password
     }
 
-    fn verify_id(id: &str) -> Result<(), Box<dyn error::Error>> {
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    pub fn set_email(&mut self, email: &str) -> Result<(), Error> {
+        let email = Self::normalize_email(email);
+        Self::verify_email(&email)?;
+        self.email = email;
+
+        Ok(())
+    }
+
+    pub fn set_password(&mut self, password: &Hash) {
+        self.password = password.clone();
+    }
+
+    pub fn normalize_email(email: &str) -> String {
+        let email = email.trim();
+        let (local, domain) = match email.rfind('@') {
+            Some(at) => (&email[..at], &email[at + 1..]),
+            None => return email.to_string(),
+        };
+        let domain = if domain.starts_with('[') {
+            domain.to_lowercase()
+        } else {
+            idna::domain_to_ascii(domain).unwrap_or_else(|_| domain.to_lowercase())
+        };
+
+        format!("{}@{}", local, domain)
+    }
+
+    // The local part is kept as typed, but accounts are still matched case-insensitively.
+    pub fn email_key(email: &str) -> String {
+        Self::normalize_email(email).to_lowercase()
+    }
+
+    fn verify_id(id: &str) -> Result<(), Error> {
         match verify_not_empty(id) {
             Ok(_) => Ok(()),
-            Err(_) => Err(From::from("id id should not be empty")),
+            Err(_) => Err(Error::Validation(String::from("id id should not be empty"))),
         }
     }
 
-    fn verify_email(email: &str) -> Result<(), Box<dyn error::Error>> {
-        match verify_not_empty(email) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(From::from("email id should not be empty")),
+    fn verify_email(email: &str) -> Result<(), Error> {
+        let invalid = |reason: &str| Err(Error::Validation(format!("email {}", reason)));
+        if verify_not_empty(email).is_err() {
+            return invalid("should not be empty");
+        }
+        if email.len() > 254 {
+            return invalid("should be at most 254 bytes");
+        }
+
+        let (local, domain) = match email.rfind('@') {
+            Some(at) => (&email[..at], &email[at + 1..]),
+            None => return invalid("should contain @"),
+        };
+        if local.is_empty() || local.len() > 64 {
+            return invalid("local part should be between 1 and 64 bytes");
+        }
+        if !is_email_local_part(local) {
+            return invalid("local part is malformed");
         }
+        if !is_email_domain(domain) {
+            return invalid("domain is malformed");
+        }
+
+        Ok(())
     }
 }
 
@@ -62,23 +217,42 @@ impl User {
 pub struct Hash(String);
 
 impl Hash {
-    pub fn new(plain: &str) -> Result<Self, Box<dyn error::Error>> {
+    pub const MAX_BYTES: usize = 72;
+
+    // A hash of the default cost that matches no password anyone is asked for, so checking a
+    // password of an unknown user takes as long as checking a real one.
+    const DUMMY: &'static str = "$2b$12$juevOGVImylkgg8CYw4Hue5sPFTfvuB5THN.m/TliNPr9K2Ex4GkW";
+
+    pub fn new(plain: &str) -> Result<Self, Error> {
         Self::verify_plain_password(plain)?;
 
         let hashed = bcrypt::hash(plain, bcrypt::DEFAULT_COST)?;
         Ok(Self(hashed))
     }
 
-    pub fn verify(&self, plain: &str) -> Result<bool, Box<dyn error::Error>> {
+    pub fn verify(&self, plain: &str) -> Result<bool, Error> {
         let valid = bcrypt::verify(plain, &self.0)?;
         Ok(valid)
     }
 
-    fn verify_plain_password(plain: &str) -> Result<(), Box<dyn error::Error>> {
-        match verify_not_empty(plain) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(From::from("plain password should not be empty")),
+    pub fn dummy() -> Self {
+        Self(String::from(Self::DUMMY))
+    }
+
+    fn verify_plain_password(plain: &str) -> Result<(), Error> {
+        if verify_not_empty(plain).is_err() {
+            return Err(Error::Validation(String::from(
+                "plain password should not be empty",
+            )));
+        }
+        if plain.len() > Self::MAX_BYTES {
+            return Err(Error::Validation(format!(
+                "plain password should be at most {} bytes",
+                Self::MAX_BYTES
+            )));
         }
+
+        Ok(())
     }
 }
 
@@ -88,13 +262,158 @@ impl From<String> for Hash {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasswordPolicy {
+    pub min_len: usize,
+    pub max_bytes: usize,
+    pub min_classes: usize,
+    pub blocklist: BTreeSet<String>,
+}
+
+impl PasswordPolicy {
+    const COMMON_PASSWORDS: &'static str = include_str!("common_passwords.txt");
+
+    pub fn check(&self, plain: &str) -> Result<(), Error> {
+        let mut violations = Vec::new();
+        if plain.chars().count() < self.min_len {
+            violations.push(format!(
+                "password should be at least {} characters",
+                self.min_len
+            ));
+        }
+        let max_bytes = self.max_bytes.min(Hash::MAX_BYTES);
+        if plain.len() > max_bytes {
+            violations.push(format!("password should be at most {} bytes", max_bytes));
+        }
+        if Self::classes(plain) < self.min_classes {
+            violations.push(format!(
+                "password should contain at least {} of lowercase letters, uppercase letters, digits and symbols",
+                self.min_classes
+            ));
+        }
+        if self.blocklist.contains(&plain.to_lowercase()) {
+            violations.push(String::from("password is too common"));
+        }
+
+        if !violations.is_empty() {
+            return Err(Error::Validation(violations.join("; ")));
+        }
+
+        Ok(())
+    }
+
+    pub fn parse_blocklist(list: &str) -> BTreeSet<String> {
+        list.lines()
+            .map(|password| password.trim().to_lowercase())
+            .filter(|password| !password.is_empty())
+            .collect()
+    }
+
+    fn classes(plain: &str) -> usize {
+        let has = |f: fn(char) -> bool| plain.chars().any(f);
+        [
+            has(char::is_lowercase),
+            has(char::is_uppercase),
+            has(|c| c.is_ascii_digit()),
+            has(|c| !c.is_alphanumeric()),
+        ]
+        .iter()
+        .filter(|has| **has)
+        .count()
+    }
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_len: 8,
+            max_bytes: Hash::MAX_BYTES,
+            min_classes: 2,
+            blocklist: Self::parse_blocklist(Self::COMMON_PASSWORDS),
+        }
+    }
+}
+
+pub trait LoginAttemptRepo {
+    fn find_by_email(&self, email: &str) -> Result<Option<LoginAttempts>, Error>;
+    fn save(&mut self, attempts: &LoginAttempts) -> Result<(), Error>;
+    fn delete(&mut self, email: &str) -> Result<(), Error>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoginAttempts {
+    email: String,
+    failures: u32,
+    blocked_until: DateTime<Utc>,
+}
+
+impl LoginAttempts {
+    pub fn new(email: &str, now: DateTime<Utc>) -> Self {
+        Self {
+            email: User::email_key(email),
+            failures: 0,
+            blocked_until: now,
+        }
+    }
+
+    pub fn email(&self) -> &String {
+        &self.email
+    }
+
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    pub fn blocked_until(&self) -> DateTime<Utc> {
+        self.blocked_until
+    }
+
+    pub fn remaining(&self, now: DateTime<Utc>) -> Option<Duration> {
+        if now < self.blocked_until {
+            Some(self.blocked_until - now)
+        } else {
+            None
+        }
+    }
+
+    pub fn fail(&mut self, now: DateTime<Utc>, policy: &LoginPolicy) {
+        self.failures = self.failures.saturating_add(1);
+
+        self.blocked_until = if self.failures >= policy.max_failures {
+            let lockouts = (self.failures - policy.max_failures).min(16);
+            now + (policy.lockout * 2i32.pow(lockouts)).min(policy.max_lockout)
+        } else {
+            now + policy.backoff * 2i32.pow(self.failures.min(16) - 1)
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoginPolicy {
+    pub max_failures: u32,
+    pub backoff: Duration,
+    pub lockout: Duration,
+    pub max_lockout: Duration,
+}
+
+impl Default for LoginPolicy {
+    fn default() -> Self {
+        Self {
+            max_failures: 5,
+            backoff: Duration::seconds(1),
+            lockout: Duration::minutes(15),
+            max_lockout: Duration::days(1),
+        }
+    }
+}
+
 pub trait TaskRepo {
-    fn next_id(&self) -> Result<String, Box<dyn error::Error>>;
-    fn get(&self, user_id: &str) -> Result<Vec<Task>, Box<dyn error::Error>>;
-    fn find_of_user(&self, id: &str, user_id: &str) -> Result<Option<Task>, Box<dyn error::Error>>;
-    fn save(&mut self, task: &Task) -> Result<(), Box<dyn error::Error>>;
-    fn delete(&mut self, id: &str) -> Result<(), Box<dyn error::Error>>;
-    fn delete_of_user(&mut self, user_id: &str) -> Result<(), Box<dyn error::Error>>;
+    fn next_id(&self) -> Result<String, Error>;
+    fn query(&self, user_id: &str, query: &TaskQuery) -> Result<Vec<Task>, Error>;
+    fn find_of_user(&self, id: &str, user_id: &str) -> Result<Option<Task>, Error>;
+    fn save(&mut self, task: &Task) -> Result<(), Error>;
+    fn delete(&mut self, id: &str) -> Result<(), Error>;
+    fn delete_of_user(&mut self, user_id: &str) -> Result<(), Error>;
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -103,10 +422,25 @@ pub struct Task {
     user_id: String,
     name: String,
     completed: bool,
+    due: Option<DateTime<Utc>>,
+    priority: Priority,
+    tags: BTreeSet<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+    position: i64,
 }
 
 impl Task {
-    pub fn new(id: &str, user_id: &str, name: &str) -> Result<Self, Box<dyn error::Error>> {
+    pub fn new(
+        id: &str,
+        user_id: &str,
+        name: &str,
+        due: Option<DateTime<Utc>>,
+        priority: Priority,
+        created_at: DateTime<Utc>,
+        position: i64,
+    ) -> Result<Self, Error> {
         Self::verify_id(id)?;
         Self::verify_user_id(user_id)?;
         Self::verify_name(name)?;
@@ -116,22 +450,16 @@ impl Task {
             user_id: user_id.to_string(),
             name: name.to_string(),
             completed: false,
+            due,
+            priority,
+            tags: BTreeSet::new(),
+            created_at,
+            updated_at: created_at,
+            completed_at: None,
+            position,
         })
     }
 
-    pub fn recover(id: &str, user_id: &str, name: &str, completed: bool) -> Self {
-        Self::verify_id(id).unwrap();
-        Self::verify_user_id(user_id).unwrap();
-        Self::verify_name(name).unwrap();
-
-        Self {
-            id: id.to_string(),
-            user_id: user_id.to_string(),
-            name: name.to_string(),
-            completed: completed,
-        }
-    }
-
     pub fn id(&self) -> &String {
         &self.id
     }
@@ -148,32 +476,418 @@ impl Task {
         self.completed
     }
 
-    pub fn complete(&mut self) {
-        self.completed = true;
+    pub fn due(&self) -> Option<DateTime<Utc>> {
+        self.due
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    pub fn tags(&self) -> &BTreeSet<String> {
+        &self.tags
     }
 
-    fn verify_id(id: &str) -> Result<(), Box<dyn error::Error>> {
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+
+    pub fn completed_at(&self) -> Option<DateTime<Utc>> {
+        self.completed_at
+    }
+
+    pub fn position(&self) -> i64 {
+        self.position
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+
+    pub fn complete(&mut self, now: DateTime<Utc>) {
+        if !self.completed {
+            self.completed = true;
+            self.completed_at = Some(now);
+        }
+    }
+
+    pub fn reopen(&mut self) {
+        self.completed = false;
+        self.completed_at = None;
+    }
+
+    pub fn touch(&mut self, now: DateTime<Utc>) {
+        self.updated_at = now;
+    }
+
+    pub fn rename(&mut self, name: &str) -> Result<(), Error> {
+        Self::verify_name(name)?;
+        self.name = name.to_string();
+
+        Ok(())
+    }
+
+    pub fn set_due(&mut self, due: Option<DateTime<Utc>>) {
+        self.due = due;
+    }
+
+    pub fn set_priority(&mut self, priority: Priority) {
+        self.priority = priority;
+    }
+
+    pub fn set_position(&mut self, position: i64) {
+        self.position = position;
+    }
+
+    pub fn set_user_id(&mut self, user_id: &str) {
+        self.user_id = user_id.to_string();
+    }
+
+    pub fn add_tag(&mut self, tag: &str) -> Result<(), Error> {
+        let tag = Self::normalize_tag(tag)?;
+        self.tags.insert(tag);
+
+        Ok(())
+    }
+
+    pub fn remove_tag(&mut self, tag: &str) -> Result<(), Error> {
+        let tag = Self::normalize_tag(tag)?;
+        self.tags.remove(&tag);
+
+        Ok(())
+    }
+
+    // Names are searched and sorted case-insensitively, with the same Unicode folding everywhere.
+    pub fn name_key(name: &str) -> String {
+        name.to_lowercase()
+    }
+
+    pub fn normalize_tag(tag: &str) -> Result<String, Error> {
+        let tag = tag
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join("-")
+            .to_lowercase();
+        Self::verify_tag(&tag)?;
+
+        Ok(tag)
+    }
+
+    fn verify_id(id: &str) -> Result<(), Error> {
         match verify_not_empty(id) {
             Ok(_) => Ok(()),
-            Err(_) => Err(From::from("id should not be empty")),
+            Err(_) => Err(Error::Validation(String::from("id should not be empty"))),
         }
     }
 
-    fn verify_user_id(user_id: &str) -> Result<(), Box<dyn error::Error>> {
+    fn verify_user_id(user_id: &str) -> Result<(), Error> {
         match verify_not_empty(user_id) {
             Ok(_) => Ok(()),
-            Err(_) => Err(From::from("user id should not be empty")),
+            Err(_) => Err(Error::Validation(String::from(
+                "user id should not be empty",
+            ))),
         }
     }
 
-    fn verify_name(name: &str) -> Result<(), Box<dyn error::Error>> {
+    fn verify_name(name: &str) -> Result<(), Error> {
         match verify_not_empty(name) {
             Ok(_) => Ok(()),
-            Err(_) => Err(From::from("name should not be empty")),
+            Err(_) => Err(Error::Validation(String::from("name should not be empty"))),
+        }
+    }
+
+    fn verify_tag(tag: &str) -> Result<(), Error> {
+        match verify_not_empty(tag) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(Error::Validation(String::from("tag should not be empty"))),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    #[default]
+    None,
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    pub const NAMES: [&'static str; 5] = ["none", "low", "medium", "high", "urgent"];
+
+    pub fn new(name: &str) -> Result<Self, Error> {
+        match name {
+            "none" => Ok(Priority::None),
+            "low" => Ok(Priority::Low),
+            "medium" => Ok(Priority::Medium),
+            "high" => Ok(Priority::High),
+            "urgent" => Ok(Priority::Urgent),
+            _ => Err(Error::Validation(format!(
+                "priority should be one of {}",
+                Self::NAMES.join(", ")
+            ))),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        Self::NAMES[*self as usize]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagFilter {
+    All(BTreeSet<String>),
+    Any(BTreeSet<String>),
+}
+
+impl TagFilter {
+    pub fn all(tags: &[&str]) -> Result<Self, Error> {
+        Ok(TagFilter::All(Self::normalize_tags(tags)?))
+    }
+
+    pub fn any(tags: &[&str]) -> Result<Self, Error> {
+        Ok(TagFilter::Any(Self::normalize_tags(tags)?))
+    }
+
+    pub fn tags(&self) -> &BTreeSet<String> {
+        match self {
+            TagFilter::All(tags) | TagFilter::Any(tags) => tags,
+        }
+    }
+
+    pub fn matches(&self, tags: &BTreeSet<String>) -> bool {
+        match self {
+            TagFilter::All(filter) => filter.is_subset(tags),
+            TagFilter::Any(filter) => filter.is_empty() || !filter.is_disjoint(tags),
+        }
+    }
+
+    fn normalize_tags(tags: &[&str]) -> Result<BTreeSet<String>, Error> {
+        tags.iter().map(|tag| Task::normalize_tag(tag)).collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskQuery {
+    pub completed: Option<bool>,
+    pub name_contains: Option<String>,
+    pub tags: Option<TagFilter>,
+    pub due_before: Option<DateTime<Utc>>,
+    pub due_after: Option<DateTime<Utc>>,
+    pub sort: TaskSort,
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
+
+impl TaskQuery {
+    pub fn matches(&self, task: &Task) -> bool {
+        if let Some(completed) = self.completed {
+            if task.is_completed() != completed {
+                return false;
+            }
+        }
+        if let Some(name) = &self.name_contains {
+            if !Task::name_key(task.name()).contains(&Task::name_key(name)) {
+                return false;
+            }
+        }
+        if let Some(filter) = &self.tags {
+            if !filter.matches(task.tags()) {
+                return false;
+            }
+        }
+        if self.due_before.is_some() || self.due_after.is_some() {
+            let due = match task.due() {
+                Some(due) => due,
+                None => return false,
+            };
+            if self.due_before.is_some_and(|before| due >= before) {
+                return false;
+            }
+            if self.due_after.is_some_and(|after| due < after) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    pub fn apply<I: IntoIterator<Item = Task>>(&self, tasks: I) -> Vec<Task> {
+        let mut tasks: Vec<Task> = tasks
+            .into_iter()
+            .filter(|task| self.matches(task))
+            .collect();
+        tasks.sort_by(|a, b| self.sort.compare(a, b));
+
+        tasks
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TaskSort {
+    pub key: TaskSortKey,
+    pub direction: SortDirection,
+}
+
+impl TaskSort {
+    pub fn new(key: TaskSortKey) -> Self {
+        Self {
+            key,
+            direction: key.default_direction(),
+        }
+    }
+
+    pub fn compare(&self, a: &Task, b: &Task) -> Ordering {
+        let ord = match self.key {
+            TaskSortKey::Priority => a.priority().cmp(&b.priority()),
+            TaskSortKey::Due => match (a.due(), b.due()) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(_), None) => return Ordering::Less,
+                (None, Some(_)) => return Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+            TaskSortKey::Name => Task::name_key(a.name()).cmp(&Task::name_key(b.name())),
+            TaskSortKey::Position => a.position().cmp(&b.position()),
+            TaskSortKey::Created => a.created_at().cmp(&b.created_at()),
+            TaskSortKey::Updated => a.updated_at().cmp(&b.updated_at()),
+        };
+        let ord = match self.direction {
+            SortDirection::Asc => ord,
+            SortDirection::Desc => ord.reverse(),
+        };
+
+        ord.then_with(|| a.position().cmp(&b.position()))
+            .then_with(|| a.created_at().cmp(&b.created_at()))
+            .then_with(|| a.id().cmp(b.id()))
+    }
+}
+
+impl Default for TaskSort {
+    fn default() -> Self {
+        Self::new(TaskSortKey::Position)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TaskSortKey {
+    Position,
+    Created,
+    Updated,
+    Priority,
+    Due,
+    Name,
+}
+
+impl TaskSortKey {
+    pub const NAMES: [&'static str; 6] =
+        ["position", "created", "updated", "priority", "due", "name"];
+
+    pub fn new(name: &str) -> Result<Self, Error> {
+        match name {
+            "position" => Ok(TaskSortKey::Position),
+            "created" => Ok(TaskSortKey::Created),
+            "updated" => Ok(TaskSortKey::Updated),
+            "priority" => Ok(TaskSortKey::Priority),
+            "due" => Ok(TaskSortKey::Due),
+            "name" => Ok(TaskSortKey::Name),
+            _ => Err(Error::Validation(format!(
+                "sort key should be one of {}",
+                Self::NAMES.join(", ")
+            ))),
+        }
+    }
+
+    pub fn default_direction(&self) -> SortDirection {
+        match self {
+            TaskSortKey::Priority => SortDirection::Desc,
+            TaskSortKey::Updated => SortDirection::Desc,
+            TaskSortKey::Position | TaskSortKey::Created | TaskSortKey::Due | TaskSortKey::Name => {
+                SortDirection::Asc
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+impl SortDirection {
+    pub const NAMES: [&'static str; 2] = ["asc", "desc"];
+
+    pub fn new(name: &str) -> Result<Self, Error> {
+        match name {
+            "asc" => Ok(SortDirection::Asc),
+            "desc" => Ok(SortDirection::Desc),
+            _ => Err(Error::Validation(format!(
+                "sort direction should be one of {}",
+                Self::NAMES.join(", ")
+            ))),
+        }
+    }
+}
+
+fn is_email_local_part(local: &str) -> bool {
+    let is_char = |c: char| !c.is_control() && (!c.is_ascii() || c.is_ascii_graphic() || c == ' ');
+    if local.len() >= 2 && local.starts_with('"') && local.ends_with('"') {
+        let mut chars = local[1..local.len() - 1].chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some(c) if is_char(c) => {}
+                    _ => return false,
+                },
+                '"' => return false,
+                c if is_char(c) => {}
+                _ => return false,
+            }
+        }
+
+        return true;
+    }
+
+    local.split('.').all(|atom| {
+        !atom.is_empty()
+            && atom.chars().all(|c| {
+                c.is_ascii_alphanumeric()
+                    || "!#$%&'*+-/=?^_`{|}~".contains(c)
+                    || (!c.is_ascii() && !c.is_control())
+            })
+    })
+}
+
+fn is_email_domain(domain: &str) -> bool {
+    if let Some(literal) = domain.strip_prefix('[').and_then(|d| d.strip_suffix(']')) {
+        return match literal.strip_prefix("ipv6:") {
+            Some(addr) => addr.parse::<Ipv6Addr>().is_ok(),
+            None => literal.parse::<Ipv4Addr>().is_ok(),
+        };
+    }
+
+    let labels: Vec<&str> = domain.split('.').collect();
+    domain.len() <= 253
+        && labels.len() >= 2
+        && labels.iter().all(|label| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+        && !labels[labels.len() - 1].chars().all(|c| c.is_ascii_digit())
+}
+
 fn verify_not_empty(s: &str) -> Result<(), String> {
     match s {
         "" => Err(String::from("empty")),
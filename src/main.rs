@@ -1,29 +1,170 @@
 extern crate todo;
 
+use chrono::Duration;
+use std::env;
+use std::fs;
+use std::path::Path;
 use std::process;
+use std::str::FromStr;
 use todo::gateway::controller;
 use todo::gateway::controller::cli;
-use todo::gateway::presenter::text;
-use todo::infra::file;
+use todo::infra::{file, sqlite, state, system};
+
+type Backend = (
+    Box<dyn todo::UserRepo>,
+    Box<dyn todo::TaskRepo>,
+    Box<dyn todo::LoginAttemptRepo>,
+    Box<dyn controller::SessionManager>,
+);
 
 fn main() {
     let workspace = "./";
-    let mut user_repo: Box<dyn todo::UserRepo> = Box::new(file::UserRepo::new(&workspace).unwrap());
-    let mut task_repo: Box<dyn todo::TaskRepo> = Box::new(file::TaskRepo::new(&workspace).unwrap());
-    let user_renderer: Box<dyn controller::UserRenderer> = Box::new(text::Text {});
-    let task_renderer: Box<dyn controller::TaskRenderer> = Box::new(text::Text {});
-    let mut session_manager: Box<dyn controller::SessionManager> =
-        Box::new(file::SessionManager::new(&workspace).unwrap());
+    let state_dir = or_exit(state::state_dir());
+    let session_policy = or_exit(session_policy());
+    let (mut user_repo, mut task_repo, mut login_attempt_repo, mut session_manager) =
+        or_exit(backend(workspace, &state_dir, session_policy));
+    let clock = system::Clock {};
+    let password_policy = or_exit(password_policy());
+    let login_policy = todo::LoginPolicy::default();
+    let history_path = state::history_path(&state_dir).ok();
 
     let mut app = cli::App::new(
         &mut user_repo,
         &mut task_repo,
-        &user_renderer,
-        &task_renderer,
+        &mut login_attempt_repo,
         &mut session_manager,
+        &clock,
+        &password_policy,
+        &login_policy,
+        history_path.as_deref(),
     );
     if let Err(err) = app.run() {
-        eprintln!("{}", err);
-        process::exit(1);
+        process::exit(exit_code(&err));
+    }
+}
+
+fn or_exit<T>(result: Result<T, todo::Error>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(exit_code(&err));
+        }
+    }
+}
+
+fn exit_code(err: &todo::Error) -> i32 {
+    match err {
+        todo::Error::Internal(_) => 1,
+        todo::Error::Validation(_) => 2,
+        todo::Error::NotFound(_) => 3,
+        todo::Error::Unauthorized(_) => 4,
+        todo::Error::Conflict(_) => 5,
+        todo::Error::Storage(_) => 6,
+        todo::Error::TooManyAttempts(..) => 7,
+    }
+}
+
+fn backend(
+    workspace: &str,
+    state_dir: &Path,
+    session_policy: controller::SessionPolicy,
+) -> Result<Backend, todo::Error> {
+    match env::var("TODO_BACKEND").unwrap_or_default().as_str() {
+        "" | "file" => Ok((
+            Box::new(file::UserRepo::new(workspace)?),
+            Box::new(file::TaskRepo::new(workspace)?),
+            Box::new(file::LoginAttemptRepo::new(workspace)?),
+            Box::new(file::SessionManager::new(
+                workspace,
+                state_dir,
+                Box::new(system::Clock {}),
+                session_policy,
+            )?),
+        )),
+        "sqlite" => Ok((
+            Box::new(sqlite::UserRepo::new(workspace)?),
+            Box::new(sqlite::TaskRepo::new(workspace)?),
+            Box::new(sqlite::LoginAttemptRepo::new(workspace)?),
+            Box::new(sqlite::SessionManager::new(
+                workspace,
+                state_dir,
+                Box::new(system::Clock {}),
+                session_policy,
+            )?),
+        )),
+        backend => Err(todo::Error::Validation(format!(
+            "unknown backend: {}",
+            backend
+        ))),
+    }
+}
+
+fn password_policy() -> Result<todo::PasswordPolicy, todo::Error> {
+    let mut policy = todo::PasswordPolicy::default();
+    if let Some(min_len) = env_value("TODO_PASSWORD_MIN_LEN")? {
+        policy.min_len = min_len;
+    }
+    if let Some(max_bytes) = env_value("TODO_PASSWORD_MAX_BYTES")? {
+        policy.max_bytes = max_bytes;
+    }
+    if let Some(min_classes) = env_value("TODO_PASSWORD_MIN_CLASSES")? {
+        if min_classes > 4 {
+            return Err(todo::Error::Validation(String::from(
+                "invalid TODO_PASSWORD_MIN_CLASSES: there are only 4 character classes",
+            )));
+        }
+        policy.min_classes = min_classes;
+    }
+    if let Some(path) = env_value::<String>("TODO_PASSWORD_BLOCKLIST")? {
+        let list = fs::read_to_string(&path)
+            .map_err(|err| todo::Error::from(err).context("failed to read password blocklist"))?;
+        policy.blocklist = todo::PasswordPolicy::parse_blocklist(&list);
+    }
+
+    Ok(policy)
+}
+
+fn session_policy() -> Result<controller::SessionPolicy, todo::Error> {
+    let mut policy = controller::SessionPolicy::default();
+    if let Some(idle_timeout) = env_duration("TODO_SESSION_IDLE_TIMEOUT")? {
+        policy.idle_timeout = idle_timeout;
+    }
+    if let Some(lifetime) = env_duration("TODO_SESSION_LIFETIME")? {
+        policy.lifetime = lifetime;
+    }
+
+    Ok(policy)
+}
+
+fn env_duration(name: &str) -> Result<Option<Duration>, todo::Error> {
+    let value = match env_value::<String>(name)? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    // Sessions add these to their timestamps, so they are kept far from chrono's limits.
+    match cli::parse_duration(&value) {
+        Ok(duration) if duration <= Duration::weeks(520) => Ok(Some(duration)),
+        Ok(_) => Err(todo::Error::Validation(format!(
+            "invalid {}: {}: must be at most 520w",
+            name, value
+        ))),
+        _ => Err(todo::Error::Validation(format!(
+            "invalid {}: {}: expected such as 30m, 12h, 3d or 2w",
+            name, value
+        ))),
+    }
+}
+
+fn env_value<T: FromStr>(name: &str) -> Result<Option<T>, todo::Error> {
+    match env::var(name) {
+        Ok(value) if !value.trim().is_empty() => match value.trim().parse() {
+            Ok(value) => Ok(Some(value)),
+            Err(_) => Err(todo::Error::Validation(format!(
+                "invalid {}: {}",
+                name, value
+            ))),
+        },
+        _ => Ok(None),
     }
 }
@@ -1,18 +1,34 @@
 use super::*;
+use chrono::{DateTime, Duration, Utc};
 
 pub struct CreateUser<'a> {
     repo: &'a mut Box<dyn UserRepo>,
+    clock: &'a dyn Clock,
+    password_policy: &'a PasswordPolicy,
 }
 
 impl<'a> CreateUser<'a> {
-    pub fn new(repo: &'a mut Box<dyn UserRepo>) -> Self {
-        Self { repo: repo }
+    pub fn new(
+        repo: &'a mut Box<dyn UserRepo>,
+        clock: &'a dyn Clock,
+        password_policy: &'a PasswordPolicy,
+    ) -> Self {
+        Self {
+            repo,
+            clock,
+            password_policy,
+        }
     }
 
-    pub fn invoke(&mut self, email: &str, password: &str) -> Result<User, Box<dyn error::Error>> {
+    pub fn invoke(&mut self, email: &str, password: &str) -> Result<User, Error> {
+        self.password_policy.check(password)?;
+        if self.repo.find_by_email(email)?.is_some() {
+            return Err(Error::email_taken());
+        }
+
         let id = self.repo.next_id()?;
         let password = Hash::new(password)?;
-        let user = User::new(&id, email, &password)?;
+        let user = User::new(&id, email, &password, self.clock.now())?;
 
         self.repo.save(&user)?;
 
@@ -20,25 +36,250 @@ impl<'a> CreateUser<'a> {
     }
 }
 
+pub struct GetUser<'a> {
+    repo: &'a dyn UserRepo,
+}
+
+impl<'a> GetUser<'a> {
+    pub fn new(repo: &'a dyn UserRepo) -> Self {
+        Self { repo }
+    }
+
+    pub fn invoke(&self, id: &str) -> Result<User, Error> {
+        match self.repo.find_by_id(id)? {
+            Some(user) => Ok(user),
+            None => Err(Error::NotFound(String::from("no such user"))),
+        }
+    }
+}
+
 pub struct AuthenticateUser<'a> {
-    repo: &'a Box<dyn UserRepo>,
+    user_repo: &'a dyn UserRepo,
+    login_attempt_repo: &'a mut Box<dyn LoginAttemptRepo>,
+    clock: &'a dyn Clock,
+    login_policy: &'a LoginPolicy,
 }
 
 impl<'a> AuthenticateUser<'a> {
-    pub fn new(repo: &'a Box<dyn UserRepo>) -> Self {
+    pub fn new(
+        user_repo: &'a dyn UserRepo,
+        login_attempt_repo: &'a mut Box<dyn LoginAttemptRepo>,
+        clock: &'a dyn Clock,
+        login_policy: &'a LoginPolicy,
+    ) -> Self {
+        Self {
+            user_repo,
+            login_attempt_repo,
+            clock,
+            login_policy,
+        }
+    }
+
+    pub fn invoke(&mut self, email: &str, password: &str) -> Result<Option<User>, Error> {
+        let now = self.clock.now();
+        let email = User::email_key(email);
+        let attempts = find_unblocked_attempts(self.login_attempt_repo.as_ref(), &email, now)?;
+
+        // Unknown emails are checked against a dummy hash and throttled like known ones, so
+        // neither the response time nor the lockout tells whether an account exists.
+        let user = self.user_repo.find_by_email(&email)?;
+        let verified = match &user {
+            Some(user) => user.password().verify(password)?,
+            None => {
+                Hash::dummy().verify(password)?;
+                false
+            }
+        };
+        record_attempt(
+            self.login_attempt_repo,
+            attempts,
+            verified,
+            now,
+            self.login_policy,
+        )?;
+
+        Ok(user.filter(|_| verified))
+    }
+}
+
+pub struct ChangePassword<'a> {
+    user_repo: &'a mut Box<dyn UserRepo>,
+    login_attempt_repo: &'a mut Box<dyn LoginAttemptRepo>,
+    clock: &'a dyn Clock,
+    password_policy: &'a PasswordPolicy,
+    login_policy: &'a LoginPolicy,
+}
+
+impl<'a> ChangePassword<'a> {
+    pub fn new(
+        user_repo: &'a mut Box<dyn UserRepo>,
+        login_attempt_repo: &'a mut Box<dyn LoginAttemptRepo>,
+        clock: &'a dyn Clock,
+        password_policy: &'a PasswordPolicy,
+        login_policy: &'a LoginPolicy,
+    ) -> Self {
+        Self {
+            user_repo,
+            login_attempt_repo,
+            clock,
+            password_policy,
+            login_policy,
+        }
+    }
+
+    pub fn invoke(&mut self, id: &str, current: &str, password: &str) -> Result<User, Error> {
+        let now = self.clock.now();
+        let mut user = GetUser::new(self.user_repo.as_ref()).invoke(id)?;
+        let email = User::email_key(user.email());
+        let attempts = find_unblocked_attempts(self.login_attempt_repo.as_ref(), &email, now)?;
+
+        let verified = user.password().verify(current)?;
+        record_attempt(
+            self.login_attempt_repo,
+            attempts,
+            verified,
+            now,
+            self.login_policy,
+        )?;
+        if !verified {
+            return Err(Error::Unauthorized(String::from(
+                "current password is incorrect",
+            )));
+        }
+        self.password_policy.check(password)?;
+
+        user.set_password(&Hash::new(password)?);
+        self.user_repo.save(&user)?;
+
+        Ok(user)
+    }
+}
+
+fn find_unblocked_attempts(
+    repo: &dyn LoginAttemptRepo,
+    email: &str,
+    now: DateTime<Utc>,
+) -> Result<LoginAttempts, Error> {
+    let attempts = match repo.find_by_email(email)? {
+        Some(attempts) => attempts,
+        None => LoginAttempts::new(email, now),
+    };
+    if let Some(remaining) = attempts.remaining(now) {
+        let remaining = Duration::seconds((remaining.num_milliseconds() + 999) / 1000);
+        return Err(Error::TooManyAttempts(
+            format!(
+                "too many failed login attempts; try again in {}",
+                format_remaining(remaining)
+            ),
+            remaining,
+        ));
+    }
+
+    Ok(attempts)
+}
+
+fn record_attempt(
+    repo: &mut Box<dyn LoginAttemptRepo>,
+    mut attempts: LoginAttempts,
+    verified: bool,
+    now: DateTime<Utc>,
+    policy: &LoginPolicy,
+) -> Result<(), Error> {
+    if !verified {
+        attempts.fail(now, policy);
+        return repo.save(&attempts);
+    }
+    if attempts.failures() > 0 {
+        repo.delete(attempts.email())?;
+    }
+
+    Ok(())
+}
+
+pub struct ChangeEmail<'a> {
+    repo: &'a mut Box<dyn UserRepo>,
+}
+
+impl<'a> ChangeEmail<'a> {
+    pub fn new(repo: &'a mut Box<dyn UserRepo>) -> Self {
         Self { repo }
     }
 
-    pub fn invoke(
-        &self,
-        email: &str,
-        password: &str,
-    ) -> Result<Option<User>, Box<dyn error::Error>> {
-        let user = self.repo.find_by_email(email)?;
-        match user {
-            Some(user) if user.password().verify(password)? => Ok(Some(user)),
-            _ => Ok(None),
+    pub fn invoke(&mut self, id: &str, email: &str) -> Result<User, Error> {
+        let mut user = GetUser::new(self.repo.as_ref()).invoke(id)?;
+        if let Some(other) = self.repo.find_by_email(email)? {
+            if other.id() != user.id() {
+                return Err(Error::email_taken());
+            }
         }
+
+        user.set_email(email)?;
+        self.repo.save(&user)?;
+
+        Ok(user)
+    }
+}
+
+pub struct FindDuplicateUsers<'a> {
+    repo: &'a dyn UserRepo,
+}
+
+impl<'a> FindDuplicateUsers<'a> {
+    pub fn new(repo: &'a dyn UserRepo) -> Self {
+        Self { repo }
+    }
+
+    pub fn invoke(&self) -> Result<Vec<Vec<User>>, Error> {
+        let mut groups: Vec<Vec<User>> = Vec::new();
+        for user in self.repo.find_all()? {
+            let email = User::email_key(user.email());
+            match groups
+                .iter_mut()
+                .find(|group| User::email_key(group[0].email()) == email)
+            {
+                Some(group) => group.push(user),
+                None => groups.push(vec![user]),
+            }
+        }
+        groups.retain(|group| group.len() > 1);
+
+        Ok(groups)
+    }
+}
+
+pub struct MergeUsers<'a> {
+    user_repo: &'a mut Box<dyn UserRepo>,
+    task_repo: &'a mut Box<dyn TaskRepo>,
+}
+
+impl<'a> MergeUsers<'a> {
+    pub fn new(user_repo: &'a mut Box<dyn UserRepo>, task_repo: &'a mut Box<dyn TaskRepo>) -> Self {
+        Self {
+            user_repo,
+            task_repo,
+        }
+    }
+
+    pub fn invoke(&mut self, users: &[User]) -> Result<User, Error> {
+        let mut users = users.to_vec();
+        users.sort_by_key(|user| (user.created_at(), user.id().clone()));
+        let (kept, others) = match users.split_first() {
+            Some(users) => users,
+            None => return Err(Error::Validation(String::from("no users to merge"))),
+        };
+
+        let mut position = next_position(self.task_repo.as_ref(), kept.id())?;
+        for other in others {
+            for mut task in self.task_repo.query(other.id(), &TaskQuery::default())? {
+                task.set_user_id(kept.id());
+                task.set_position(position);
+                self.task_repo.save(&task)?;
+                position += 1;
+            }
+            self.user_repo.delete(other.id())?;
+        }
+
+        Ok(kept.clone())
     }
 }
 
@@ -55,38 +296,97 @@ impl<'a> DeleteUser<'a> {
         }
     }
 
-    pub fn invoke(&mut self, id: &str) -> Result<(), Box<dyn error::Error>> {
+    pub fn invoke(&mut self, id: &str) -> Result<(), Error> {
         self.task_repo.delete_of_user(id)?;
         self.user_repo.delete(id)
     }
 }
 
-pub struct GetTasks<'a> {
-    repo: &'a Box<dyn TaskRepo>,
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DueFilter {
+    Overdue,
+    Within(Duration),
 }
 
-impl<'a> GetTasks<'a> {
-    pub fn new(repo: &'a Box<dyn TaskRepo>) -> Self {
+impl DueFilter {
+    pub fn apply(&self, query: &mut TaskQuery, now: DateTime<Utc>) -> Result<(), Error> {
+        match self {
+            DueFilter::Overdue => {
+                query.completed = Some(false);
+                query.due_before = Some(now);
+            }
+            DueFilter::Within(within) => {
+                query.completed = Some(false);
+                query.due_after = Some(now);
+                query.due_before = Some(now.checked_add_signed(*within).ok_or_else(|| {
+                    Error::Validation(String::from("invalid due window: out of range"))
+                })?);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct QueryTasks<'a> {
+    repo: &'a dyn TaskRepo,
+}
+
+impl<'a> QueryTasks<'a> {
+    pub fn new(repo: &'a dyn TaskRepo) -> Self {
+        Self { repo }
+    }
+
+    pub fn invoke(&self, user_id: &str, query: &TaskQuery) -> Result<Vec<Task>, Error> {
+        self.repo.query(user_id, query)
+    }
+}
+
+pub struct GetTask<'a> {
+    repo: &'a dyn TaskRepo,
+}
+
+impl<'a> GetTask<'a> {
+    pub fn new(repo: &'a dyn TaskRepo) -> Self {
         Self { repo }
     }
 
-    pub fn invoke(&self, user_id: &str) -> Result<Vec<Task>, Box<dyn error::Error>> {
-        self.repo.get(user_id)
+    pub fn invoke(&self, id: &str, user_id: &str) -> Result<Task, Error> {
+        match self.repo.find_of_user(id, user_id)? {
+            Some(task) => Ok(task),
+            None => Err(Error::NotFound(String::from("no such task"))),
+        }
     }
 }
 
 pub struct CreateTask<'a> {
     repo: &'a mut Box<dyn TaskRepo>,
+    clock: &'a dyn Clock,
 }
 
 impl<'a> CreateTask<'a> {
-    pub fn new(repo: &'a mut Box<dyn TaskRepo>) -> Self {
-        Self { repo }
+    pub fn new(repo: &'a mut Box<dyn TaskRepo>, clock: &'a dyn Clock) -> Self {
+        Self { repo, clock }
     }
 
-    pub fn invoke(&mut self, user_id: &str, name: &str) -> Result<Task, Box<dyn error::Error>> {
+    pub fn invoke(
+        &mut self,
+        user_id: &str,
+        name: &str,
+        due: Option<DateTime<Utc>>,
+        priority: Priority,
+    ) -> Result<Task, Error> {
         let id = self.repo.next_id()?;
-        let task = Task::new(&id, user_id, name)?;
+        let position = next_position(self.repo.as_ref(), user_id)?;
+        let task = Task::new(
+            &id,
+            user_id,
+            name,
+            due,
+            priority,
+            self.clock.now(),
+            position,
+        )?;
 
         self.repo.save(&task)?;
 
@@ -94,22 +394,41 @@ impl<'a> CreateTask<'a> {
     }
 }
 
+fn next_position(repo: &dyn TaskRepo, user_id: &str) -> Result<i64, Error> {
+    let last = repo.query(
+        user_id,
+        &TaskQuery {
+            sort: TaskSort {
+                key: TaskSortKey::Position,
+                direction: SortDirection::Desc,
+            },
+            limit: Some(1),
+            ..Default::default()
+        },
+    )?;
+
+    Ok(last.first().map_or(0, |task| task.position() + 1))
+}
+
 pub struct CompleteTask<'a> {
     repo: &'a mut Box<dyn TaskRepo>,
+    clock: &'a dyn Clock,
 }
 
 impl<'a> CompleteTask<'a> {
-    pub fn new(repo: &'a mut Box<dyn TaskRepo>) -> Self {
-        Self { repo }
+    pub fn new(repo: &'a mut Box<dyn TaskRepo>, clock: &'a dyn Clock) -> Self {
+        Self { repo, clock }
     }
 
-    pub fn invoke(&mut self, id: &str, user_id: &str) -> Result<Task, Box<dyn error::Error>> {
+    pub fn invoke(&mut self, id: &str, user_id: &str) -> Result<Task, Error> {
         let mut task = match self.repo.find_of_user(id, user_id)? {
             Some(task) => task,
-            None => return Err(From::from("no such task")),
+            None => return Err(Error::NotFound(String::from("no such task"))),
         };
 
-        task.complete();
+        let now = self.clock.now();
+        task.complete(now);
+        task.touch(now);
 
         self.repo.save(&task)?;
 
@@ -117,6 +436,137 @@ impl<'a> CompleteTask<'a> {
     }
 }
 
+pub struct UpdateTask<'a> {
+    repo: &'a mut Box<dyn TaskRepo>,
+    clock: &'a dyn Clock,
+}
+
+impl<'a> UpdateTask<'a> {
+    pub fn new(repo: &'a mut Box<dyn TaskRepo>, clock: &'a dyn Clock) -> Self {
+        Self { repo, clock }
+    }
+
+    pub fn invoke(&mut self, id: &str, user_id: &str, update: &TaskUpdate) -> Result<Task, Error> {
+        let mut task = match self.repo.find_of_user(id, user_id)? {
+            Some(task) => task,
+            None => return Err(Error::NotFound(String::from("no such task"))),
+        };
+
+        let now = self.clock.now();
+        if let Some(name) = &update.name {
+            task.rename(name)?;
+        }
+        match update.completed {
+            Some(true) => task.complete(now),
+            Some(false) => task.reopen(),
+            None => {}
+        }
+        if let Some(due) = update.due {
+            task.set_due(due);
+        }
+        if let Some(priority) = update.priority {
+            task.set_priority(priority);
+        }
+        for tag in &update.add_tags {
+            task.add_tag(tag)?;
+        }
+        for tag in &update.remove_tags {
+            task.remove_tag(tag)?;
+        }
+        task.touch(now);
+
+        self.repo.save(&task)?;
+
+        Ok(task)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TaskUpdate {
+    pub name: Option<String>,
+    pub completed: Option<bool>,
+    pub due: Option<Option<DateTime<Utc>>>,
+    pub priority: Option<Priority>,
+    pub add_tags: Vec<String>,
+    pub remove_tags: Vec<String>,
+}
+
+pub struct MoveTask<'a> {
+    repo: &'a mut Box<dyn TaskRepo>,
+    clock: &'a dyn Clock,
+}
+
+impl<'a> MoveTask<'a> {
+    pub fn new(repo: &'a mut Box<dyn TaskRepo>, clock: &'a dyn Clock) -> Self {
+        Self { repo, clock }
+    }
+
+    pub fn invoke(&mut self, id: &str, user_id: &str, to: &MoveTo) -> Result<Task, Error> {
+        let mut tasks = self.repo.query(
+            user_id,
+            &TaskQuery {
+                sort: TaskSort::new(TaskSortKey::Position),
+                ..Default::default()
+            },
+        )?;
+        let mut task = match tasks.iter().position(|task| task.id() == id) {
+            Some(i) => tasks.remove(i),
+            None => return Err(Error::NotFound(String::from("no such task"))),
+        };
+
+        let at = match to {
+            MoveTo::Top => 0,
+            MoveTo::Bottom => tasks.len(),
+            MoveTo::Before(before) => match tasks.iter().position(|task| task.id() == before) {
+                Some(i) => i,
+                None => return Err(Error::NotFound(String::from("no such task to move before"))),
+            },
+        };
+        let prev = if at > 0 { tasks.get(at - 1) } else { None };
+        let next = tasks.get(at);
+
+        match (prev, next) {
+            (None, None) => return Ok(task),
+            (None, Some(next)) => task.set_position(next.position() - 1),
+            (Some(prev), None) => task.set_position(prev.position() + 1),
+            (Some(prev), Some(next)) if next.position() - prev.position() > 1 => {
+                task.set_position(prev.position() + (next.position() - prev.position()) / 2)
+            }
+            _ => {
+                tasks.insert(at, task);
+                return self.renumber(tasks, at);
+            }
+        }
+        task.touch(self.clock.now());
+
+        self.repo.save(&task)?;
+
+        Ok(task)
+    }
+
+    fn renumber(&mut self, mut tasks: Vec<Task>, at: usize) -> Result<Task, Error> {
+        tasks[at].touch(self.clock.now());
+
+        for (position, task) in tasks.iter_mut().enumerate() {
+            if task.position() == position as i64 && position != at {
+                continue;
+            }
+
+            task.set_position(position as i64);
+            self.repo.save(task)?;
+        }
+
+        Ok(tasks.swap_remove(at))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MoveTo {
+    Top,
+    Bottom,
+    Before(String),
+}
+
 pub struct DeleteTask<'a> {
     repo: &'a mut Box<dyn TaskRepo>,
 }
@@ -126,11 +576,20 @@ impl<'a> DeleteTask<'a> {
         Self { repo }
     }
 
-    pub fn invoke(&mut self, id: &str, user_id: &str) -> Result<(), Box<dyn error::Error>> {
-        if let None = self.repo.find_of_user(id, user_id)? {
-            return Err(From::from("no such task"));
+    pub fn invoke(&mut self, id: &str, user_id: &str) -> Result<(), Error> {
+        if self.repo.find_of_user(id, user_id)?.is_none() {
+            return Err(Error::NotFound(String::from("no such task")));
         }
 
         self.repo.delete(id)
     }
 }
+
+fn format_remaining(remaining: Duration) -> String {
+    let secs = remaining.num_seconds();
+    match (secs / 60, secs % 60) {
+        (0, secs) => format!("{}s", secs),
+        (mins, 0) => format!("{}m", mins),
+        (mins, secs) => format!("{}m {}s", mins, secs),
+    }
+}
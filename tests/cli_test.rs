@@ -0,0 +1,176 @@
+extern crate todo;
+
+mod common;
+
+use chrono::Duration;
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use todo::gateway::controller::cli;
+
+#[test]
+fn parse_duration() {
+    assert_eq!(Ok(Duration::minutes(30)), cli::parse_duration("30m"));
+    assert_eq!(Ok(Duration::weeks(2)), cli::parse_duration("2w"));
+
+    for duration in &["", "3", "3y", "0d", "-3d", "99999999999999w"] {
+        match cli::parse_duration(duration) {
+            Err(todo::Error::Validation(_)) => {}
+            other => panic!("{:?} should be invalid, got {:?}", duration, other),
+        }
+    }
+}
+
+#[test]
+fn exit_with_validation_code_on_usage_errors() {
+    let dir = common::workspace("usage");
+    let todo = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_todo"))
+            .args(args)
+            .current_dir(&dir)
+            .env("XDG_STATE_HOME", dir.join("state"))
+            .output()
+            .unwrap()
+    };
+
+    assert_eq!(Some(2), todo(&["task", "bogus"]).status.code());
+    assert_eq!(
+        Some(2),
+        todo(&["task", "get", "--sort", "nope"]).status.code()
+    );
+    let output = todo(&["--output", "json", "task", "get", "--sort", "nope"]);
+    assert_eq!(Some(2), output.status.code());
+    let err: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!("validation", err["error"]["kind"]);
+
+    let output = todo(&["--help"]);
+    assert_eq!(Some(0), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("USAGE"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn merge_only_the_duplicated_emails_picked_in_repair() {
+    let dir = common::workspace("repair");
+    fs::write(
+        dir.join("store.json"),
+        r#"{"users":{
+            "first":{"id":"first","email":"test@example.com","password":"hash",
+                "created_at":"2020-01-01T00:00:00Z"},
+            "second":{"id":"second","email":"Test@Example.com","password":"hash",
+                "created_at":"2020-01-02T00:00:00Z"}
+        },"tasks":{}}"#,
+    )
+    .unwrap();
+    let todo = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_todo"))
+            .args(args)
+            .current_dir(&dir)
+            .env("XDG_STATE_HOME", dir.join("state"))
+            .output()
+            .unwrap()
+    };
+    let users = || fs::read_to_string(dir.join("store.json")).unwrap();
+
+    let output = todo(&["repair", "--help"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("deletes all but the oldest account"));
+
+    assert_eq!(Some(5), todo(&["repair"]).status.code());
+    assert_eq!(
+        Some(3),
+        todo(&[
+            "repair",
+            "--merge",
+            "TEST@example.com",
+            "--merge",
+            "nobody@example.com"
+        ])
+        .status
+        .code()
+    );
+    assert!(users().contains("second"));
+
+    assert_eq!(
+        Some(0),
+        todo(&["repair", "--merge", "TEST@example.com"])
+            .status
+            .code()
+    );
+    assert!(users().contains("first"));
+    assert!(!users().contains("second"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn change_password_from_two_stdin_lines() {
+    let dir = common::workspace("password-stdin");
+    let todo = |args: &[&str], stdin: &str| {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_todo"))
+            .args(args)
+            .current_dir(&dir)
+            .env("XDG_STATE_HOME", dir.join("state"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(stdin.as_bytes())
+            .unwrap();
+        child.wait().unwrap().code()
+    };
+    let email = ["--email", "test@example.com"];
+
+    assert_eq!(
+        Some(0),
+        todo(
+            &[&["user", "create", "--password-stdin"][..], &email].concat(),
+            "aiueo-1234\n"
+        )
+    );
+    assert_eq!(
+        Some(0),
+        todo(
+            &[&["user", "login", "--password-stdin"][..], &email].concat(),
+            "aiueo-1234\n"
+        )
+    );
+    assert_eq!(
+        Some(2),
+        todo(
+            &[
+                "user",
+                "password",
+                "--password",
+                "aiueo-1234",
+                "--password-stdin"
+            ],
+            "kakikukeko-5678\n"
+        )
+    );
+    assert_eq!(
+        Some(0),
+        todo(
+            &["user", "password", "--password-stdin"],
+            "aiueo-1234\nkakikukeko-5678\n"
+        )
+    );
+    assert_eq!(
+        Some(0),
+        todo(
+            &[
+                &["user", "login", "--password", "kakikukeko-5678"][..],
+                &email
+            ]
+            .concat(),
+            ""
+        )
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
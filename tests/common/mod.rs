@@ -0,0 +1,125 @@
+#![allow(dead_code)]
+
+use chrono::{DateTime, Duration, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+use todo::gateway::controller::{SessionManager, SessionPolicy};
+use todo::infra::state::{self, TokenFile};
+
+pub fn workspace(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("todo-test-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    dir
+}
+
+pub fn session_policy() -> SessionPolicy {
+    SessionPolicy {
+        idle_timeout: Duration::hours(1),
+        lifetime: Duration::hours(2),
+    }
+}
+
+// `manager(secs)` opens the sessions in `dir` with session_policy() and a clock `secs` after
+// `now`, and `stored()` dumps the session records as the backend keeps them.
+pub fn keep_hashed_session_records(
+    dir: &Path,
+    now: DateTime<Utc>,
+    manager: &dyn Fn(i64) -> Box<dyn SessionManager>,
+    stored: &dyn Fn() -> String,
+) {
+    let user_id = Some(String::from("test user id"));
+
+    let mut sessions = manager(0);
+    sessions.push_authenticated_user_id("test user id").unwrap();
+    sessions.select_session("work");
+    sessions.push_authenticated_user_id("test user id").unwrap();
+    let token = TokenFile::new(&dir.join("state"), dir.to_str().unwrap())
+        .unwrap()
+        .get("default")
+        .unwrap()
+        .unwrap();
+    let records = stored();
+    assert!(records.contains(&state::hash_token(&token)));
+    assert!(!records.contains(&token));
+
+    let last_used = |sessions: Box<dyn SessionManager>| {
+        sessions.find_sessions_of_user("test user id").unwrap()[0].last_used_at
+    };
+    assert_eq!(user_id, manager(30).pop_authenticated_user_id().unwrap());
+    assert_eq!(now, last_used(manager(30)));
+    assert_eq!(records, stored());
+    assert_eq!(user_id, manager(90).pop_authenticated_user_id().unwrap());
+    assert_eq!(now + Duration::seconds(90), last_used(manager(90)));
+
+    assert_eq!(user_id, manager(3000).pop_authenticated_user_id().unwrap());
+    let mut sessions = manager(3700);
+    assert_eq!(user_id, sessions.pop_authenticated_user_id().unwrap());
+    sessions.select_session("work");
+    assert_eq!(None, sessions.pop_authenticated_user_id().unwrap());
+    assert_eq!(user_id, manager(7100).pop_authenticated_user_id().unwrap());
+    assert_eq!(None, manager(7200).pop_authenticated_user_id().unwrap());
+    assert!(manager(7200)
+        .find_sessions_of_user("test user id")
+        .unwrap()
+        .is_empty());
+
+    let mut sessions = manager(7200);
+    sessions.push_authenticated_user_id("test user id").unwrap();
+    assert_eq!(user_id, sessions.pop_authenticated_user_id().unwrap());
+    sessions.drop_authenticated_user_id().unwrap();
+    assert_eq!(None, sessions.pop_authenticated_user_id().unwrap());
+}
+
+pub fn drop_other_sessions_of_user(sessions: &mut dyn SessionManager) {
+    for (name, user_id) in &[
+        ("work", "test user id"),
+        ("other", "other user id"),
+        ("default", "test user id"),
+    ] {
+        sessions.select_session(name);
+        sessions.push_authenticated_user_id(user_id).unwrap();
+    }
+
+    sessions
+        .drop_other_sessions_of_user("test user id")
+        .unwrap();
+
+    let names = |user_id: &str| -> Vec<String> {
+        sessions
+            .find_sessions_of_user(user_id)
+            .unwrap()
+            .into_iter()
+            .map(|session| session.name)
+            .collect()
+    };
+    assert_eq!(vec!["default"], names("test user id"));
+    assert_eq!(vec!["other"], names("other user id"));
+    assert_eq!(
+        Some(String::from("test user id")),
+        sessions.pop_authenticated_user_id().unwrap()
+    );
+}
+
+pub fn drop_session_records_of_merged_user(sessions: &mut dyn SessionManager) {
+    sessions.select_session("merged");
+    sessions
+        .push_authenticated_user_id("merged user id")
+        .unwrap();
+    sessions.select_session("default");
+    sessions.push_authenticated_user_id("test user id").unwrap();
+
+    sessions
+        .drop_session_records_of_user("merged user id")
+        .unwrap();
+
+    assert!(sessions
+        .find_sessions_of_user("merged user id")
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        Some(String::from("test user id")),
+        sessions.pop_authenticated_user_id().unwrap()
+    );
+}
@@ -0,0 +1,242 @@
+extern crate todo;
+
+mod common;
+
+use chrono::{DateTime, Duration, Utc};
+use std::fs;
+use std::path::Path;
+use std::thread;
+use todo::infra::{file, memory};
+use todo::usecase;
+use todo::{Priority, Task, TaskQuery, TaskRepo, UserRepo};
+
+#[test]
+fn backfill_created_at_of_legacy_tasks() {
+    let dir = common::workspace("legacy");
+    fs::write(
+        dir.join("store.json"),
+        r#"{"users":{},"tasks":{"t1":{"id":"t1","user_id":"u1","name":"legacy","completed":false}}}"#,
+    )
+    .unwrap();
+    let modified: DateTime<Utc> = fs::metadata(dir.join("store.json"))
+        .unwrap()
+        .modified()
+        .unwrap()
+        .into();
+
+    let mut repo = file::TaskRepo::new(dir.to_str().unwrap()).unwrap();
+    let task = repo.find_of_user("t1", "u1").unwrap().unwrap();
+    assert_eq!(modified, task.created_at());
+    assert_eq!(modified, task.updated_at());
+
+    repo.save(&task).unwrap();
+    let tasks = repo.query("u1", &TaskQuery::default()).unwrap();
+    assert_eq!(vec![task], tasks);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn keep_writes_made_with_the_same_stamp() {
+    let dir = common::workspace("same-stamp");
+    let path = dir.join("store.json");
+    let now = Utc::now();
+    let mut repo = file::TaskRepo::new(dir.to_str().unwrap()).unwrap();
+    let task = |id: &str, name: &str| {
+        Task::new(id, "test user id", name, None, Priority::None, now, 0).unwrap()
+    };
+    repo.save(&task("t1", "task a")).unwrap();
+
+    let modified = fs::metadata(&path).unwrap().modified().unwrap();
+    let store = fs::read_to_string(&path)
+        .unwrap()
+        .replace("task a", "task b");
+    fs::write(&path, store).unwrap();
+    fs::File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+
+    repo.save(&task("t2", "task c")).unwrap();
+    let mut names: Vec<String> = file::TaskRepo::new(dir.to_str().unwrap())
+        .unwrap()
+        .query("test user id", &TaskQuery::default())
+        .unwrap()
+        .iter()
+        .map(|task| task.name().clone())
+        .collect();
+    names.sort();
+    assert_eq!(vec!["task b", "task c"], names);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn merge_duplicated_users_of_legacy_store() {
+    let dir = common::workspace("duplicates");
+    let workspace = dir.to_str().unwrap();
+    fs::write(
+        dir.join("store.json"),
+        r#"{"users":{
+            "second":{"id":"second","email":"Test@Example.com","password":"hash",
+                "created_at":"2020-01-02T00:00:00Z"},
+            "first":{"id":"first","email":"test@example.com","password":"hash",
+                "created_at":"2020-01-01T00:00:00Z"}
+        },"tasks":{}}"#,
+    )
+    .unwrap();
+
+    let mut user_repo: Box<dyn UserRepo> = Box::new(file::UserRepo::new(workspace).unwrap());
+    let mut task_repo: Box<dyn TaskRepo> = Box::new(file::TaskRepo::new(workspace).unwrap());
+    let clock = memory::Clock::new(Utc::now());
+    for (user_id, name) in &[("first", "kept task"), ("second", "moved task")] {
+        usecase::CreateTask::new(&mut task_repo, &clock)
+            .invoke(user_id, name, None, Priority::None)
+            .unwrap();
+    }
+
+    let groups = usecase::FindDuplicateUsers::new(user_repo.as_ref())
+        .invoke()
+        .unwrap();
+    assert_eq!(1, groups.len());
+    let kept = usecase::MergeUsers::new(&mut user_repo, &mut task_repo)
+        .invoke(&groups[0])
+        .unwrap();
+    assert_eq!("first", kept.id());
+    assert_eq!(vec![kept.clone()], user_repo.find_all().unwrap());
+    let tasks = task_repo.query("first", &TaskQuery::default()).unwrap();
+    assert_eq!(
+        vec!["kept task", "moved task"],
+        tasks
+            .iter()
+            .map(|task| task.name().as_str())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![0, 1],
+        tasks.iter().map(Task::position).collect::<Vec<_>>()
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn save_tasks_from_concurrent_repos() {
+    let dir = common::workspace("concurrent");
+    let workspace = dir.to_str().unwrap().to_string();
+    let now = Utc::now();
+
+    let handles: Vec<_> = (0..2)
+        .map(|i| {
+            let workspace = workspace.clone();
+            thread::spawn(move || {
+                let mut repo = file::TaskRepo::new(&workspace).unwrap();
+                for j in 0..20 {
+                    let task = Task::new(
+                        &repo.next_id().unwrap(),
+                        "test user id",
+                        &format!("task {}-{}", i, j),
+                        None,
+                        Priority::None,
+                        now,
+                        j,
+                    )
+                    .unwrap();
+                    repo.save(&task).unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let repo = file::TaskRepo::new(&workspace).unwrap();
+    assert_eq!(
+        40,
+        repo.query("test user id", &TaskQuery::default())
+            .unwrap()
+            .len()
+    );
+    assert!(!dir.join("store.json.tmp").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn reload_store_written_by_another_repo() {
+    let dir = common::workspace("reload");
+    let workspace = dir.to_str().unwrap();
+    let mut writer = file::TaskRepo::new(workspace).unwrap();
+    let reader = file::TaskRepo::new(workspace).unwrap();
+    assert!(reader
+        .query("test user id", &TaskQuery::default())
+        .unwrap()
+        .is_empty());
+
+    let now = Utc::now();
+    for (position, name) in ["read book", "write code"].iter().enumerate() {
+        let task = Task::new(
+            &writer.next_id().unwrap(),
+            "test user id",
+            name,
+            None,
+            Priority::None,
+            now,
+            position as i64,
+        )
+        .unwrap();
+        writer.save(&task).unwrap();
+
+        let tasks = reader.query("test user id", &TaskQuery::default()).unwrap();
+        assert_eq!(position + 1, tasks.len());
+        assert_eq!(task, tasks[position]);
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+fn session_manager(dir: &Path, now: DateTime<Utc>) -> file::SessionManager {
+    file::SessionManager::new(
+        dir.to_str().unwrap(),
+        &dir.join("state"),
+        Box::new(memory::Clock::new(now)),
+        common::session_policy(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn keep_hashed_session_records() {
+    let dir = common::workspace("file-sessions");
+    let now = Utc::now();
+
+    common::keep_hashed_session_records(
+        &dir,
+        now,
+        &|secs| Box::new(session_manager(&dir, now + Duration::seconds(secs))),
+        &|| fs::read_to_string(dir.join("store.json")).unwrap(),
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn drop_other_sessions_of_user() {
+    let dir = common::workspace("file-other-sessions");
+
+    common::drop_other_sessions_of_user(&mut session_manager(&dir, Utc::now()));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn drop_session_records_of_merged_user() {
+    let dir = common::workspace("file-merged-sessions");
+
+    common::drop_session_records_of_merged_user(&mut session_manager(&dir, Utc::now()));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
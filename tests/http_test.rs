@@ -0,0 +1,321 @@
+extern crate todo;
+
+use chrono::{TimeZone, Utc};
+use serde_json::{json, Value};
+use std::io::Read;
+use tiny_http::{Header, Method, TestRequest};
+use todo::gateway::controller::{http, params};
+use todo::infra::memory;
+use todo::{
+    LoginAttemptRepo, LoginPolicy, PasswordPolicy, SortDirection, TagFilter, TaskQuery, TaskRepo,
+    TaskSort, TaskSortKey, UserRepo,
+};
+
+struct Response {
+    status: u16,
+    retry_after: Option<String>,
+    body: Value,
+}
+
+// TestRequest only takes static paths and bodies, which a test can afford to leak.
+fn send(
+    server: &mut http::Server,
+    method: Method,
+    path: &str,
+    token: Option<&str>,
+    body: Value,
+) -> Response {
+    let mut request = TestRequest::new()
+        .with_method(method)
+        .with_path(Box::leak(path.to_string().into_boxed_str()))
+        .with_body(Box::leak(body.to_string().into_boxed_str()));
+    if let Some(token) = token {
+        let value = format!("Bearer {}", token);
+        request = request.with_header(Header::from_bytes("Authorization", value).unwrap());
+    }
+
+    let response = server.respond(&mut request.into());
+    let retry_after = response
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Retry-After"))
+        .map(|header| header.value.to_string());
+    let status = response.status_code().0;
+    let mut body = String::new();
+    response.into_reader().read_to_string(&mut body).unwrap();
+
+    Response {
+        status,
+        retry_after,
+        body: if body.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(&body).unwrap()
+        },
+    }
+}
+
+fn login(server: &mut http::Server, email: &str, password: &str) -> Response {
+    send(
+        server,
+        Method::Post,
+        "/login",
+        None,
+        json!({ "email": email, "password": password }),
+    )
+}
+
+struct Backend {
+    user_repo: Box<dyn UserRepo>,
+    task_repo: Box<dyn TaskRepo>,
+    login_attempt_repo: Box<dyn LoginAttemptRepo>,
+    clock: memory::Clock,
+    password_policy: PasswordPolicy,
+    login_policy: LoginPolicy,
+}
+
+impl Backend {
+    fn new() -> Self {
+        Self {
+            user_repo: Box::new(memory::UserRepo::new()),
+            task_repo: Box::new(memory::TaskRepo::new()),
+            login_attempt_repo: Box::new(memory::LoginAttemptRepo::new()),
+            clock: memory::Clock::new(Utc::now()),
+            password_policy: PasswordPolicy::default(),
+            login_policy: LoginPolicy::default(),
+        }
+    }
+
+    fn server(&mut self) -> http::Server<'_> {
+        http::Server::new(
+            &mut self.user_repo,
+            &mut self.task_repo,
+            &mut self.login_attempt_repo,
+            &self.clock,
+            &self.password_policy,
+            &self.login_policy,
+        )
+    }
+}
+
+#[test]
+fn decode_query_value() {
+    assert_eq!(Ok(String::from("read book")), http::decode("read+book"));
+    assert_eq!(Ok(String::from("a&b=c")), http::decode("a%26b%3dc"));
+    assert_eq!(Ok(String::from("日本")), http::decode("%E6%97%A5%E6%9C%AC"));
+
+    for value in &["%", "%2", "%zz", "%+f", "%-1", "%FF"] {
+        match http::decode(value) {
+            Err(todo::Error::Validation(_)) => {}
+            other => panic!("{:?} should be invalid, got {:?}", value, other),
+        }
+    }
+}
+
+#[test]
+fn parse_task_query() {
+    assert_eq!(Ok(TaskQuery::default()), http::task_query(""));
+
+    let query = http::task_query(
+        "completed=false&name=read%20book&tag=Home&tag=deep+work&any_tag\
+         &due_before=2020-01-02T00:00:00Z&sort=due&order=desc&limit=10&offset=20",
+    )
+    .unwrap();
+    assert_eq!(
+        TaskQuery {
+            completed: Some(false),
+            name_contains: Some(String::from("read book")),
+            tags: Some(TagFilter::any(&["home", "deep-work"]).unwrap()),
+            due_before: Some(Utc.with_ymd_and_hms(2020, 1, 2, 0, 0, 0).unwrap()),
+            due_after: None,
+            sort: TaskSort {
+                key: TaskSortKey::Due,
+                direction: SortDirection::Desc,
+            },
+            limit: Some(10),
+            offset: 20,
+        },
+        query
+    );
+
+    for query in &[
+        "completed=maybe",
+        "due_after=tomorrow",
+        "sort=size",
+        "order=up",
+        "limit=-1",
+        "tag=+",
+        "color=red",
+    ] {
+        match http::task_query(query) {
+            Err(todo::Error::Validation(_)) => {}
+            other => panic!("{:?} should be invalid, got {:?}", query, other),
+        }
+    }
+}
+
+#[test]
+fn normalize_new_task_tags() {
+    let task: params::NewTask =
+        serde_json::from_str(r#"{"name":"read book","tags":["Deep Work","home"]}"#).unwrap();
+    assert_eq!(
+        Ok(vec![String::from("deep-work"), String::from("home")]),
+        task.tags()
+    );
+
+    let task: params::NewTask =
+        serde_json::from_str(r#"{"name":"bad tags","tags":["home","  "]}"#).unwrap();
+    match task.tags() {
+        Err(todo::Error::Validation(_)) => {}
+        other => panic!("blank tag should be invalid, got {:?}", other),
+    }
+}
+
+#[test]
+fn report_conflicts_and_throttled_logins() {
+    let mut backend = Backend::new();
+    let mut server = backend.server();
+    let credentials = json!({ "email": "test@example.com", "password": "aiueo-1234" });
+
+    let res = send(
+        &mut server,
+        Method::Post,
+        "/users",
+        None,
+        credentials.clone(),
+    );
+    assert_eq!(201, res.status);
+    assert_eq!(json!("test@example.com"), res.body["email"]);
+    let res = send(&mut server, Method::Post, "/users", None, credentials);
+    assert_eq!(409, res.status);
+    assert_eq!(json!("conflict"), res.body["error"]["kind"]);
+
+    let res = login(&mut server, "test@example.com", "wrong");
+    assert_eq!((401, None), (res.status, res.retry_after));
+    let res = login(&mut server, "test@example.com", "aiueo-1234");
+    assert_eq!(429, res.status);
+    assert_eq!(Some(String::from("1")), res.retry_after);
+}
+
+#[test]
+fn serve_tasks_to_authenticated_users() {
+    let mut backend = Backend::new();
+    let mut server = backend.server();
+    let credentials = json!({ "email": "test@example.com", "password": "aiueo-1234" });
+    send(&mut server, Method::Post, "/users", None, credentials);
+    let res = login(&mut server, "test@example.com", "aiueo-1234");
+    assert_eq!(200, res.status);
+    let token = res.body["token"].as_str().unwrap().to_string();
+    let token = Some(token.as_str());
+
+    for (method, path) in &[(Method::Get, "/tasks"), (Method::Post, "/logout")] {
+        let res = send(&mut server, method.clone(), path, None, Value::Null);
+        assert_eq!(401, res.status);
+        let res = send(&mut server, method.clone(), path, Some("nope"), Value::Null);
+        assert_eq!(401, res.status);
+    }
+
+    let res = send(
+        &mut server,
+        Method::Post,
+        "/tasks",
+        token,
+        json!({ "name": "read book", "priority": "high" }),
+    );
+    assert_eq!(201, res.status);
+    let path = format!("/tasks/{}", res.body["id"].as_str().unwrap());
+
+    let res = send(&mut server, Method::Get, &path, token, Value::Null);
+    assert_eq!(
+        (200, json!("read book")),
+        (res.status, res.body["name"].clone())
+    );
+    let res = send(
+        &mut server,
+        Method::Patch,
+        &path,
+        token,
+        json!({ "name": "write book" }),
+    );
+    assert_eq!(
+        (200, json!("write book")),
+        (res.status, res.body["name"].clone())
+    );
+    let complete = format!("{}/complete", path);
+    let res = send(&mut server, Method::Post, &complete, token, Value::Null);
+    assert_eq!(
+        (200, json!(true)),
+        (res.status, res.body["completed"].clone())
+    );
+    let res = send(
+        &mut server,
+        Method::Get,
+        "/tasks?completed=true",
+        token,
+        Value::Null,
+    );
+    assert_eq!((200, 1), (res.status, res.body.as_array().unwrap().len()));
+    let res = send(
+        &mut server,
+        Method::Get,
+        "/tasks?sort=size",
+        token,
+        Value::Null,
+    );
+    assert_eq!(400, res.status);
+
+    assert_eq!(
+        204,
+        send(&mut server, Method::Delete, &path, token, Value::Null).status
+    );
+    assert_eq!(
+        404,
+        send(&mut server, Method::Get, &path, token, Value::Null).status
+    );
+    assert_eq!(
+        404,
+        send(&mut server, Method::Get, "/nope", token, Value::Null).status
+    );
+
+    assert_eq!(
+        204,
+        send(&mut server, Method::Post, "/logout", token, Value::Null).status
+    );
+    assert_eq!(
+        401,
+        send(&mut server, Method::Get, "/tasks", token, Value::Null).status
+    );
+}
+
+#[test]
+fn revoke_every_token_of_deleted_user() {
+    let mut backend = Backend::new();
+    let mut server = backend.server();
+    let credentials = json!({ "email": "test@example.com", "password": "aiueo-1234" });
+    send(&mut server, Method::Post, "/users", None, credentials);
+    let tokens: Vec<String> = (0..2)
+        .map(|_| {
+            login(&mut server, "test@example.com", "aiueo-1234").body["token"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+
+    let res = send(
+        &mut server,
+        Method::Delete,
+        "/users/me",
+        Some(&tokens[0]),
+        Value::Null,
+    );
+    assert_eq!(204, res.status);
+    for token in &tokens {
+        let res = send(&mut server, Method::Get, "/tasks", Some(token), Value::Null);
+        assert_eq!(401, res.status);
+    }
+    assert_eq!(
+        401,
+        login(&mut server, "test@example.com", "aiueo-1234").status
+    );
+}
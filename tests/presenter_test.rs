@@ -0,0 +1,186 @@
+extern crate todo;
+
+use chrono::{Duration, TimeZone, Utc};
+use serde_json::json;
+use todo::gateway::presenter::json::Json;
+use todo::gateway::presenter::table::Table;
+use todo::gateway::presenter::template::Template;
+use todo::gateway::presenter::truncate;
+use todo::{Error, Hash, Priority, Task, User};
+
+fn tasks() -> Vec<Task> {
+    let now = Utc.with_ymd_and_hms(2020, 1, 2, 3, 4, 5).unwrap();
+    let mut report = Task::new(
+        "task0001xyz",
+        "test user id",
+        "write a very long report",
+        None,
+        Priority::High,
+        now,
+        0,
+    )
+    .unwrap();
+    report.add_tag("work").unwrap();
+    report.add_tag("Deep Work").unwrap();
+    let mut book = Task::new(
+        "task0002xyz",
+        "test user id",
+        "read",
+        None,
+        Priority::None,
+        now,
+        1,
+    )
+    .unwrap();
+    book.complete(now);
+
+    vec![report, book]
+}
+
+#[test]
+fn render_task_with_template() {
+    let task = Task::new(
+        "test task id",
+        "test user id",
+        "write code",
+        None,
+        Priority::High,
+        Utc::now(),
+        0,
+    )
+    .unwrap();
+    let render = |template: &str| Template::new(template).map(|template| template.render(&task));
+
+    assert_eq!(
+        Ok(String::from("{write code}\thigh\n\\ [ ]")),
+        render("{{{name}}}\\t{ priority }\\n\\\\ {status}")
+    );
+    assert_eq!(Ok(String::from("}} {{")), render("}}}} {{{{"));
+    assert_eq!(Ok(String::new()), render("{due}"));
+}
+
+#[test]
+fn parse_invalid_template() {
+    let message = |template: &str| match Template::new(template) {
+        Ok(_) => panic!("should have failed to parse {}", template),
+        Err(err) => err.message().to_string(),
+    };
+
+    assert!(message("{nope}").starts_with("invalid template: unknown placeholder {nope}, "));
+    assert_eq!("invalid template: unclosed {", message("{name"));
+    assert_eq!("invalid template: unclosed {", message("{{{name"));
+    assert_eq!(
+        "invalid template: unmatched }, use }} for a literal }",
+        message("name}")
+    );
+    assert_eq!("invalid template: unknown escape \\x", message("\\x"));
+    assert_eq!("invalid template: trailing \\", message("{name}\\"));
+}
+
+#[test]
+fn render_table() {
+    let row = |cells: [&str; 6], widths: [usize; 6]| {
+        let cells: Vec<String> = cells
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:<1$}", cell, width))
+            .collect();
+        cells.join("  ").trim_end().to_string()
+    };
+    let header = ["ID", "DONE", "NAME", "DUE", "PRIORITY", "TAGS"];
+
+    let widths = [8, 4, 24, 3, 8, 14];
+    assert_eq!(
+        vec![
+            row(header, widths),
+            row(
+                [
+                    "task0001",
+                    "[ ]",
+                    "write a very long report",
+                    "",
+                    "high",
+                    "deep-work,work"
+                ],
+                widths
+            ),
+            row(["task0002", "[x]", "read", "", "", ""], widths),
+        ],
+        Table::new(None).render(&tasks())
+    );
+
+    let lines = Table::new(Some(60)).render(&tasks());
+    assert_eq!(60, lines[1].chars().count());
+    assert!(lines[1].contains("  write a very…  "));
+
+    let widths = [8, 4, 8, 3, 8, 8];
+    assert_eq!(
+        row(
+            ["task0001", "[ ]", "write a…", "", "high", "deep-wo…"],
+            widths
+        ),
+        Table::new(Some(40)).render(&tasks())[1]
+    );
+}
+
+#[test]
+fn truncate_to_width() {
+    assert_eq!("hello", truncate("hello", 5));
+    assert_eq!("hel…", truncate("hello", 4));
+    assert_eq!("日…", truncate("日本語", 2));
+    assert_eq!("", truncate("hello", 0));
+}
+
+#[test]
+fn render_json() {
+    let task = &tasks()[1];
+    assert_eq!(
+        json!({
+            "id": "task0002xyz",
+            "user_id": "test user id",
+            "name": "read",
+            "completed": true,
+            "due": null,
+            "priority": "none",
+            "tags": [],
+            "position": 1,
+            "created_at": "2020-01-02T03:04:05Z",
+            "updated_at": "2020-01-02T03:04:05Z",
+            "completed_at": "2020-01-02T03:04:05Z",
+        }),
+        Json::task(task)
+    );
+    assert_eq!(
+        json!(["deep-work", "work"]),
+        Json::task(&tasks()[0])["tags"]
+    );
+
+    let user = User::new(
+        "test user id",
+        "test@example.com",
+        &Hash::from(String::from("hash")),
+        task.created_at(),
+    )
+    .unwrap();
+    let user = Json::user(&user);
+    assert_eq!(json!("test@example.com"), user["email"]);
+    assert_eq!(None, user.get("password"));
+
+    assert_eq!(
+        json!({ "error": { "kind": "not_found", "message": "no such task" } }),
+        Json::error(&Error::NotFound(String::from("no such task")))
+    );
+    assert_eq!(
+        json!({
+            "error": {
+                "kind": "too_many_attempts",
+                "message": "too many failed login attempts",
+                "retry_after": 90,
+            }
+        }),
+        Json::error(&Error::TooManyAttempts(
+            String::from("too many failed login attempts"),
+            Duration::seconds(90)
+        ))
+    );
+}
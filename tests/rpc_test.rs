@@ -0,0 +1,153 @@
+extern crate todo;
+
+use chrono::Utc;
+use serde_json::{json, Value};
+use todo::gateway::controller::rpc::Server;
+use todo::gateway::controller::{Session, SessionManager};
+use todo::infra::memory;
+use todo::{Error, TaskQuery};
+
+struct StaticSession(Option<String>);
+
+impl SessionManager for StaticSession {
+    fn select_session(&mut self, _: &str) {}
+
+    fn push_authenticated_user_id(&mut self, user_id: &str) -> Result<(), Error> {
+        self.0 = Some(user_id.to_string());
+        Ok(())
+    }
+
+    fn pop_authenticated_user_id(&self) -> Result<Option<String>, Error> {
+        Ok(self.0.clone())
+    }
+
+    fn drop_authenticated_user_id(&mut self) -> Result<(), Error> {
+        self.0 = None;
+        Ok(())
+    }
+
+    fn find_sessions_of_user(&self, _: &str) -> Result<Vec<Session>, Error> {
+        Ok(Vec::new())
+    }
+
+    fn drop_sessions_of_user(&mut self, _: &str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn drop_session_records_of_user(&mut self, _: &str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn drop_other_sessions_of_user(&mut self, _: &str) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+fn serve(repo: &mut Box<dyn todo::TaskRepo>, user_id: Option<&str>, input: &str) -> Vec<Value> {
+    let clock = memory::Clock::new(Utc::now());
+    let session = StaticSession(user_id.map(String::from));
+    let mut output = Vec::new();
+    Server::new(repo, &session, &clock)
+        .serve(input.as_bytes(), &mut output)
+        .unwrap();
+
+    String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn create_and_query_tasks() {
+    let mut repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
+
+    let responses = serve(
+        &mut repo,
+        Some("test user id"),
+        concat!(
+            r#"{"jsonrpc":"2.0","method":"task.create","params":{"name":"read book","tags":["Deep Work"]},"id":1}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","method":"task.query","id":2}"#,
+            "\n",
+        ),
+    );
+
+    assert_eq!(2, responses.len());
+    assert_eq!(json!(1), responses[0]["id"]);
+    assert_eq!(json!("read book"), responses[0]["result"]["name"]);
+    assert_eq!(json!(["deep-work"]), responses[0]["result"]["tags"]);
+    assert_eq!(json!(2), responses[1]["id"]);
+    assert_eq!(1, responses[1]["result"].as_array().unwrap().len());
+}
+
+#[test]
+fn create_no_task_with_invalid_tags() {
+    let mut repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
+
+    let responses = serve(
+        &mut repo,
+        Some("test user id"),
+        r#"{"jsonrpc":"2.0","method":"task.create","params":{"name":"rpc-bad","tags":[""]},"id":1}"#,
+    );
+
+    assert_eq!(json!(-32602), responses[0]["error"]["code"]);
+    assert!(repo
+        .query("test user id", &TaskQuery::default())
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn reply_to_null_id_but_not_to_notification() {
+    let mut repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
+
+    let responses = serve(
+        &mut repo,
+        Some("test user id"),
+        concat!(
+            r#"{"jsonrpc":"2.0","method":"task.query"}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","method":"task.query","id":null}"#,
+            "\n",
+            r#"[{"jsonrpc":"2.0","method":"task.query"},{"jsonrpc":"2.0","method":"task.nope","id":"a"}]"#,
+            "\n",
+        ),
+    );
+
+    assert_eq!(2, responses.len());
+    assert_eq!(Value::Null, responses[0]["id"]);
+    assert_eq!(json!([]), responses[0]["result"]);
+    assert_eq!(
+        json!([{
+            "jsonrpc": "2.0",
+            "error": { "code": -32601, "message": "method not found" },
+            "id": "a",
+        }]),
+        responses[1]
+    );
+}
+
+#[test]
+fn report_malformed_and_unauthorized_requests() {
+    let mut repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
+
+    let responses = serve(
+        &mut repo,
+        None,
+        concat!(
+            "{not json\n",
+            r#"{"jsonrpc":"1.0","method":"task.query","id":1}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","method":"task.query","id":2}"#,
+            "\n",
+        ),
+    );
+
+    assert_eq!(3, responses.len());
+    assert_eq!(json!(-32700), responses[0]["error"]["code"]);
+    assert_eq!(json!(-32600), responses[1]["error"]["code"]);
+    assert_eq!(json!(1), responses[1]["id"]);
+    assert_eq!(json!(-32002), responses[2]["error"]["code"]);
+    assert_eq!(json!("unauthorized"), responses[2]["error"]["data"]["kind"]);
+}
@@ -0,0 +1,58 @@
+extern crate todo;
+
+use chrono::{Duration, Utc};
+use todo::gateway::controller::{Session, SessionPolicy};
+use todo::infra::memory;
+use todo::Clock;
+
+fn policy() -> SessionPolicy {
+    SessionPolicy {
+        idle_timeout: Duration::hours(1),
+        lifetime: Duration::days(1),
+    }
+}
+
+#[test]
+fn expire_idle_session() {
+    let clock = memory::Clock::new(Utc::now());
+    let now = clock.now();
+    let mut session = Session::new("default", "hash", "test user id", now, &policy());
+    assert_eq!(now + Duration::hours(1), session.valid_until());
+    assert!(!session.is_expired(now + Duration::minutes(59)));
+    assert!(session.is_expired(now + Duration::hours(1)));
+
+    assert!(session.touch(now + Duration::minutes(30)));
+    assert_eq!(now + Duration::minutes(90), session.valid_until());
+    assert!(!session.is_expired(now + Duration::hours(1)));
+    assert!(session.is_expired(now + Duration::minutes(90)));
+}
+
+#[test]
+fn expire_session_after_lifetime() {
+    let clock = memory::Clock::new(Utc::now());
+    let now = clock.now();
+    let mut session = Session::new("default", "hash", "test user id", now, &policy());
+
+    let mut used_at = now;
+    while used_at + Duration::minutes(50) < now + Duration::days(1) {
+        used_at += Duration::minutes(50);
+        assert!(!session.is_expired(used_at));
+        assert!(session.touch(used_at));
+    }
+
+    assert_eq!(now + Duration::days(1), session.valid_until());
+    assert!(!session.is_expired(now + Duration::days(1) - Duration::seconds(1)));
+    assert!(session.is_expired(now + Duration::days(1)));
+}
+
+#[test]
+fn touch_session_at_most_once_a_minute() {
+    let clock = memory::Clock::new(Utc::now());
+    let now = clock.now();
+    let mut session = Session::new("default", "hash", "test user id", now, &policy());
+
+    assert!(!session.touch(now + Duration::seconds(59)));
+    assert_eq!(now, session.last_used_at);
+    assert!(session.touch(now + Duration::minutes(1)));
+    assert_eq!(now + Duration::minutes(1), session.last_used_at);
+}
@@ -0,0 +1,96 @@
+extern crate todo;
+
+mod common;
+
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use todo::gateway::controller::shell;
+
+fn words(words: &[&str]) -> Vec<String> {
+    words.iter().map(|word| word.to_string()).collect()
+}
+
+#[test]
+fn split_words() {
+    assert_eq!(Ok(Vec::new()), shell::split_words("   "));
+    assert_eq!(
+        Ok(words(&["task", "create", "--name", "read book"])),
+        shell::split_words(r#"  task create --name "read book" "#)
+    );
+    assert_eq!(
+        Ok(words(&["--name", "it's \"here\"", ""])),
+        shell::split_words(r#"--name 'it'\''s "here"' """#)
+    );
+    assert_eq!(
+        Ok(words(&["a b", "c\\d"])),
+        shell::split_words(r#"a\ b "c\\d""#)
+    );
+
+    for line in &["--name \"read book", "--name 'read", "trailing \\"] {
+        match shell::split_words(line) {
+            Err(todo::Error::Validation(_)) => {}
+            other => panic!("{:?} should be invalid, got {:?}", line, other),
+        }
+    }
+}
+
+#[test]
+fn has_secret() {
+    assert!(shell::has_secret(&words(&[
+        "user",
+        "login",
+        "--password",
+        "aiueo-1234"
+    ])));
+    assert!(shell::has_secret(&words(&[
+        "user",
+        "password",
+        "--new-password=aiueo-1234"
+    ])));
+    assert!(!shell::has_secret(&words(&[
+        "user",
+        "login",
+        "--password-stdin"
+    ])));
+    assert!(!shell::has_secret(&words(&[
+        "task", "create", "--name", "password"
+    ])));
+}
+
+#[test]
+fn keep_session_flag_to_its_own_line() {
+    let dir = common::workspace("shell-session");
+    let mut shell = Command::new(env!("CARGO_BIN_EXE_todo"))
+        .arg("shell")
+        .current_dir(&dir)
+        .env("XDG_STATE_HOME", dir.join("state"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    shell
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(
+            concat!(
+                "user create --email test@example.com --password aiueo-1234\n",
+                "user login --email test@example.com --password aiueo-1234\n",
+                "task create --name 'read book'\n",
+                "--session work task get\n",
+                "task get --output json\n",
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+    let output = shell.wait_with_output().unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(1, stderr.matches("authentication is required").count());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(r#""name":"read book""#), "{}", stdout);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
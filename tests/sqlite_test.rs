@@ -0,0 +1,288 @@
+extern crate todo;
+
+mod common;
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::Connection;
+use std::fs;
+use std::path::Path;
+use todo::infra::{memory, sqlite};
+use todo::{
+    Hash, Priority, SortDirection, TagFilter, Task, TaskQuery, TaskRepo, TaskSort, TaskSortKey,
+    User, UserRepo,
+};
+
+fn names(tasks: Vec<Task>) -> Vec<String> {
+    tasks.iter().map(|task| task.name().clone()).collect()
+}
+
+#[test]
+fn query_tasks_in_order_with_paging() {
+    let mut repo = sqlite::TaskRepo::open_in_memory().unwrap();
+    let (user_id, now) = ("test user id", Utc::now());
+    let tasks = [
+        ("write report", Priority::Low, 3),
+        ("read book", Priority::Urgent, 1),
+        ("write code", Priority::High, 2),
+        ("walk dog", Priority::Low, 0),
+    ];
+    for (position, (name, priority, days)) in tasks.iter().enumerate() {
+        let task = Task::new(
+            &repo.next_id().unwrap(),
+            user_id,
+            name,
+            Some(now + Duration::days(*days)),
+            *priority,
+            now,
+            position as i64,
+        )
+        .unwrap();
+        repo.save(&task).unwrap();
+    }
+    let other = Task::new(
+        "other",
+        "other user id",
+        "other task",
+        None,
+        Priority::None,
+        now,
+        0,
+    );
+    repo.save(&other.unwrap()).unwrap();
+
+    let query = |query: TaskQuery| names(repo.query(user_id, &query).unwrap());
+
+    assert_eq!(
+        vec!["write report", "read book", "write code", "walk dog"],
+        query(TaskQuery::default())
+    );
+    assert_eq!(
+        vec!["read book", "write code", "write report", "walk dog"],
+        query(TaskQuery {
+            sort: TaskSort::new(TaskSortKey::Priority),
+            ..Default::default()
+        })
+    );
+    assert_eq!(
+        vec!["write code", "read book"],
+        query(TaskQuery {
+            sort: TaskSort {
+                key: TaskSortKey::Due,
+                direction: SortDirection::Desc,
+            },
+            limit: Some(2),
+            offset: 1,
+            ..Default::default()
+        })
+    );
+    assert_eq!(
+        vec!["walk dog"],
+        query(TaskQuery {
+            offset: 3,
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn query_tasks_by_tags() {
+    let mut repo = sqlite::TaskRepo::open_in_memory().unwrap();
+    let (user_id, now) = ("test user id", Utc::now());
+    let tasks = [
+        ("write report", vec!["work", "urgent"]),
+        ("write code", vec!["work"]),
+        ("read book", vec!["home"]),
+        ("walk dog", vec![]),
+    ];
+    for (position, (name, tags)) in tasks.iter().enumerate() {
+        let mut task = Task::new(
+            &repo.next_id().unwrap(),
+            user_id,
+            name,
+            None,
+            Priority::None,
+            now,
+            position as i64,
+        )
+        .unwrap();
+        for tag in tags {
+            task.add_tag(tag).unwrap();
+        }
+        repo.save(&task).unwrap();
+    }
+
+    let query = |repo: &sqlite::TaskRepo, tags: TagFilter| {
+        names(
+            repo.query(
+                user_id,
+                &TaskQuery {
+                    tags: Some(tags),
+                    ..Default::default()
+                },
+            )
+            .unwrap(),
+        )
+    };
+
+    assert_eq!(
+        vec!["write report"],
+        query(&repo, TagFilter::all(&["Work", "urgent"]).unwrap())
+    );
+    assert_eq!(
+        vec!["write report", "write code", "read book"],
+        query(&repo, TagFilter::any(&["work", "home"]).unwrap())
+    );
+
+    let mut task = repo.query(user_id, &TaskQuery::default()).unwrap()[0].clone();
+    task.remove_tag("urgent").unwrap();
+    repo.save(&task).unwrap();
+    assert!(query(&repo, TagFilter::all(&["urgent"]).unwrap()).is_empty());
+    assert_eq!(
+        task.tags(),
+        repo.find_of_user(task.id(), user_id)
+            .unwrap()
+            .unwrap()
+            .tags()
+    );
+}
+
+#[test]
+fn query_non_ascii_names_like_memory_repo() {
+    let mut sqlite: Box<dyn TaskRepo> = Box::new(sqlite::TaskRepo::open_in_memory().unwrap());
+    let mut memory: Box<dyn TaskRepo> = Box::new(memory::TaskRepo::new());
+    let (user_id, now) = ("test user id", Utc::now());
+    for repo in &mut [&mut sqlite, &mut memory] {
+        for (position, name) in ["Ärger", "äpfel", "Zebra", "apple"].iter().enumerate() {
+            let task = Task::new(
+                &format!("t{}", position),
+                user_id,
+                name,
+                None,
+                Priority::None,
+                now,
+                position as i64,
+            )
+            .unwrap();
+            repo.save(&task).unwrap();
+        }
+    }
+
+    for query in &[
+        TaskQuery {
+            sort: TaskSort::new(TaskSortKey::Name),
+            ..Default::default()
+        },
+        TaskQuery {
+            name_contains: Some(String::from("ÄR")),
+            ..Default::default()
+        },
+    ] {
+        assert_eq!(
+            names(memory.query(user_id, query).unwrap()),
+            names(sqlite.query(user_id, query).unwrap())
+        );
+    }
+    assert_eq!(
+        vec!["apple", "Zebra", "äpfel", "Ärger"],
+        names(
+            sqlite
+                .query(
+                    user_id,
+                    &TaskQuery {
+                        sort: TaskSort::new(TaskSortKey::Name),
+                        ..Default::default()
+                    }
+                )
+                .unwrap()
+        )
+    );
+}
+
+#[test]
+fn save_users_with_unique_emails() {
+    let mut repo = sqlite::UserRepo::open_in_memory().unwrap();
+    let (password, now) = (Hash::from(String::from("hash")), Utc::now());
+
+    let mut user = User::new("first", "test@example.com", &password, now).unwrap();
+    repo.save(&user).unwrap();
+    user.set_password(&Hash::from(String::from("other hash")));
+    repo.save(&user).unwrap();
+    assert_eq!(Some(user.clone()), repo.find_by_id("first").unwrap());
+
+    let other = User::new("second", " TEST@example.com", &password, now).unwrap();
+    assert_eq!("TEST@example.com", other.email());
+    assert_eq!(Err(todo::Error::email_taken()), repo.save(&other));
+    assert_eq!(vec![user.clone()], repo.find_all().unwrap());
+
+    let other = User::new("second", "other@example.com", &password, now).unwrap();
+    repo.save(&other).unwrap();
+    assert_eq!(
+        Some(other.clone()),
+        repo.find_by_email("Other@Example.com").unwrap()
+    );
+
+    user.set_email("other@example.com").unwrap();
+    assert_eq!(Err(todo::Error::email_taken()), repo.save(&user));
+
+    let other = User::new("third", "Ärger@example.com", &password, now).unwrap();
+    repo.save(&other).unwrap();
+    assert_eq!(
+        Some(other.clone()),
+        repo.find_by_email("ärger@example.com").unwrap()
+    );
+    let other = User::new("fourth", "ärger@example.com", &password, now).unwrap();
+    assert_eq!(Err(todo::Error::email_taken()), repo.save(&other));
+}
+
+fn session_manager(dir: &Path, now: DateTime<Utc>) -> sqlite::SessionManager {
+    sqlite::SessionManager::new(
+        dir.to_str().unwrap(),
+        &dir.join("state"),
+        Box::new(memory::Clock::new(now)),
+        common::session_policy(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn keep_hashed_session_records() {
+    let dir = common::workspace("sqlite-sessions");
+    let now = Utc::now();
+
+    common::keep_hashed_session_records(
+        &dir,
+        now,
+        &|secs| Box::new(session_manager(&dir, now + Duration::seconds(secs))),
+        &|| {
+            let conn = Connection::open(dir.join("store.sqlite3")).unwrap();
+            let hashes: Vec<String> = conn
+                .prepare("SELECT token_hash FROM session_records ORDER BY name")
+                .unwrap()
+                .query_map(rusqlite::params![], |row| row.get(0))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            hashes.join("\n")
+        },
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn drop_other_sessions_of_user() {
+    let dir = common::workspace("sqlite-other-sessions");
+
+    common::drop_other_sessions_of_user(&mut session_manager(&dir, Utc::now()));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn drop_session_records_of_merged_user() {
+    let dir = common::workspace("sqlite-merged-sessions");
+
+    common::drop_session_records_of_merged_user(&mut session_manager(&dir, Utc::now()));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,56 @@
+extern crate todo;
+
+mod common;
+
+use std::fs;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use todo::infra::state::{self, TokenFile};
+
+#[test]
+fn token_file_round_trip() {
+    let root = common::workspace("tokens");
+    let (dir, workspace, other) = (root.join("state"), root.join("a"), root.join("b"));
+    fs::create_dir_all(&workspace).unwrap();
+    fs::create_dir_all(&other).unwrap();
+
+    let tokens = TokenFile::new(&dir, workspace.to_str().unwrap()).unwrap();
+    assert_eq!(None, tokens.get("default").unwrap());
+    tokens.set("default", "token 1").unwrap();
+    tokens.set("work", "token 2").unwrap();
+    tokens.set("default", "token 3").unwrap();
+    assert_eq!(
+        Some(String::from("token 3")),
+        tokens.get("default").unwrap()
+    );
+    assert_eq!(Some(String::from("token 2")), tokens.get("work").unwrap());
+
+    let other = TokenFile::new(&dir, other.to_str().unwrap()).unwrap();
+    assert_eq!(None, other.get("default").unwrap());
+    other.set("default", "token 4").unwrap();
+
+    tokens.remove("default").unwrap();
+    assert_eq!(None, tokens.get("default").unwrap());
+    assert_eq!(Some(String::from("token 2")), tokens.get("work").unwrap());
+    assert_eq!(Some(String::from("token 4")), other.get("default").unwrap());
+
+    #[cfg(unix)]
+    {
+        let mode = |path: PathBuf| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(0o700, mode(dir.clone()));
+        assert_eq!(0o600, mode(dir.join("sessions.json")));
+    }
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn hash_token() {
+    let hash = state::hash_token("token");
+
+    assert_eq!(64, hash.len());
+    assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(hash, state::hash_token("token"));
+    assert_ne!(hash, state::hash_token("other token"));
+}
@@ -0,0 +1,13 @@
+extern crate todo;
+
+use todo::gateway::controller::tui;
+
+#[test]
+fn fit_by_display_width() {
+    assert_eq!("[ ] read", tui::fit("[ ] read book", 8));
+    assert_eq!("[ ] 本を", tui::fit("[ ] 本を読む", 9));
+    assert_eq!("[ ] 本を", tui::fit("[ ] 本を読む", 8));
+    assert_eq!("🍣 ", tui::fit("🍣 sushi", 3));
+    assert_eq!("read", tui::fit("read", 10));
+    assert_eq!("", tui::fit("本", 1));
+}
@@ -1,14 +1,22 @@
 extern crate todo;
 
+use chrono::{Duration, Utc};
 use todo::infra::memory;
 use todo::usecase;
+use todo::Clock;
+use todo::{
+    LoginPolicy, PasswordPolicy, Priority, SortDirection, TagFilter, TaskQuery, TaskSort,
+    TaskSortKey,
+};
 
 #[test]
 fn create_user() {
+    let clock = memory::Clock::new(Utc::now());
+    let policy = PasswordPolicy::default();
     let mut repo: Box<dyn todo::UserRepo> = Box::new(memory::UserRepo::new());
 
-    let (email, password) = ("test@example.com", "aiueo");
-    let user = usecase::CreateUser::new(&mut repo)
+    let (email, password) = ("test@example.com", "aiueo-1234");
+    let user = usecase::CreateUser::new(&mut repo, &clock, &policy)
         .invoke(email, password)
         .expect("should have created user");
 
@@ -17,18 +25,183 @@ fn create_user() {
         .password()
         .verify(password)
         .expect("should have verified password"));
+    assert_eq!(clock.now(), user.created_at());
+}
+
+#[test]
+fn create_user_with_weak_password() {
+    let clock = memory::Clock::new(Utc::now());
+    let policy = PasswordPolicy::default();
+    let mut repo: Box<dyn todo::UserRepo> = Box::new(memory::UserRepo::new());
+
+    let err = usecase::CreateUser::new(&mut repo, &clock, &policy)
+        .invoke("test@example.com", "short")
+        .unwrap_err();
+    assert_eq!(
+        todo::Error::Validation(String::from(
+            "password should be at least 8 characters; password should contain at least 2 of lowercase letters, uppercase letters, digits and symbols"
+        )),
+        err
+    );
+
+    let err = usecase::CreateUser::new(&mut repo, &clock, &policy)
+        .invoke("test@example.com", "Password1")
+        .unwrap_err();
+    assert_eq!(
+        todo::Error::Validation(String::from("password is too common")),
+        err
+    );
+
+    let err = usecase::CreateUser::new(&mut repo, &clock, &policy)
+        .invoke("test@example.com", &"a1".repeat(37))
+        .unwrap_err();
+    assert_eq!(
+        todo::Error::Validation(String::from("password should be at most 72 bytes")),
+        err
+    );
+}
+
+#[test]
+fn create_user_with_custom_password_policy() {
+    let clock = memory::Clock::new(Utc::now());
+    let policy = PasswordPolicy {
+        min_len: 12,
+        min_classes: 3,
+        blocklist: PasswordPolicy::parse_blocklist("\n  Correct-Horse-Battery \n"),
+        ..Default::default()
+    };
+    let mut repo: Box<dyn todo::UserRepo> = Box::new(memory::UserRepo::new());
+
+    let err = usecase::CreateUser::new(&mut repo, &clock, &policy)
+        .invoke("test@example.com", "aiueo1234")
+        .unwrap_err();
+    assert_eq!(
+        todo::Error::Validation(String::from(
+            "password should be at least 12 characters; password should contain at least 3 of lowercase letters, uppercase letters, digits and symbols"
+        )),
+        err
+    );
+
+    let err = usecase::CreateUser::new(&mut repo, &clock, &policy)
+        .invoke("test@example.com", "correct-horse-battery")
+        .unwrap_err();
+    assert_eq!(
+        todo::Error::Validation(String::from(
+            "password should contain at least 3 of lowercase letters, uppercase letters, digits and symbols; password is too common"
+        )),
+        err
+    );
+
+    usecase::CreateUser::new(&mut repo, &clock, &policy)
+        .invoke("test@example.com", "Password1234")
+        .expect("should have created user");
+}
+
+#[test]
+fn create_user_with_taken_email() {
+    let clock = memory::Clock::new(Utc::now());
+    let policy = PasswordPolicy::default();
+    let mut repo: Box<dyn todo::UserRepo> = Box::new(memory::UserRepo::new());
+
+    let user = usecase::CreateUser::new(&mut repo, &clock, &policy)
+        .invoke(" Test@Example.com", "aiueo-1234")
+        .unwrap();
+    assert_eq!("Test@example.com", user.email());
+
+    let err = usecase::CreateUser::new(&mut repo, &clock, &policy)
+        .invoke("TEST@example.COM ", "aiueo-1234")
+        .unwrap_err();
+    assert_eq!(todo::Error::email_taken(), err);
+
+    let other = todo::User::new("other", "test@example.com", user.password(), clock.now()).unwrap();
+    assert_eq!(Err(todo::Error::email_taken()), repo.save(&other));
+    assert!(usecase::FindDuplicateUsers::new(repo.as_ref())
+        .invoke()
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn create_user_with_invalid_email() {
+    let clock = memory::Clock::new(Utc::now());
+    let policy = PasswordPolicy::default();
+    let mut repo: Box<dyn todo::UserRepo> = Box::new(memory::UserRepo::new());
+
+    for email in &[
+        "",
+        "test",
+        "@example.com",
+        "test@",
+        "test@example",
+        "test..dot@example.com",
+        "test.@example.com",
+        "te st@example.com",
+        "test@-example.com",
+        "test@example..com",
+        "test@example.123",
+        "test@[256.0.0.1]",
+    ] {
+        let err = usecase::CreateUser::new(&mut repo, &clock, &policy)
+            .invoke(email, "aiueo-1234")
+            .unwrap_err();
+        assert_eq!("validation", err.kind(), "{}", email);
+    }
+
+    for (email, normalized) in &[
+        ("Test.Name+tag@Example.COM", "Test.Name+tag@example.com"),
+        ("\"te st\"@example.com", "\"te st\"@example.com"),
+        ("test@Bücher.example", "test@xn--bcher-kva.example"),
+        ("test@[IPv6:::1]", "test@[ipv6:::1]"),
+    ] {
+        let user = usecase::CreateUser::new(&mut repo, &clock, &policy)
+            .invoke(email, "aiueo-1234")
+            .expect("should have created user");
+        assert_eq!(normalized, user.email());
+    }
+
+    let mut attempts: Box<dyn todo::LoginAttemptRepo> = Box::new(memory::LoginAttemptRepo::new());
+    let login_policy = LoginPolicy::default();
+    let user = usecase::AuthenticateUser::new(repo.as_ref(), &mut attempts, &clock, &login_policy)
+        .invoke("TEST@BÜCHER.example", "aiueo-1234")
+        .unwrap()
+        .expect("should have authenticated user");
+    assert_eq!("test@xn--bcher-kva.example", user.email());
+}
+
+#[test]
+fn get_user() {
+    let clock = memory::Clock::new(Utc::now());
+    let policy = PasswordPolicy::default();
+    let mut repo: Box<dyn todo::UserRepo> = Box::new(memory::UserRepo::new());
+    let created = usecase::CreateUser::new(&mut repo, &clock, &policy)
+        .invoke("test@example.com", "aiueo-1234")
+        .unwrap();
+
+    let user = usecase::GetUser::new(repo.as_ref())
+        .invoke(created.id())
+        .expect("should have succeeded to get user");
+    assert_eq!(created, user);
+
+    let err = usecase::GetUser::new(repo.as_ref())
+        .invoke("no such id")
+        .unwrap_err();
+    assert_eq!(todo::Error::NotFound(String::from("no such user")), err);
 }
 
 #[test]
 fn authenticate_user() {
+    let clock = memory::Clock::new(Utc::now());
+    let policy = PasswordPolicy::default();
     let mut repo: Box<dyn todo::UserRepo> = Box::new(memory::UserRepo::new());
 
-    let (email, password) = ("test@example.com", "aiueo");
-    let created = usecase::CreateUser::new(&mut repo)
+    let (email, password) = ("test@example.com", "aiueo-1234");
+    let created = usecase::CreateUser::new(&mut repo, &clock, &policy)
         .invoke(email, password)
         .unwrap();
 
-    let user = usecase::AuthenticateUser::new(&repo)
+    let mut attempts: Box<dyn todo::LoginAttemptRepo> = Box::new(memory::LoginAttemptRepo::new());
+    let login_policy = LoginPolicy::default();
+    let user = usecase::AuthenticateUser::new(repo.as_ref(), &mut attempts, &clock, &login_policy)
         .invoke(email, password)
         .expect("should have succeeded to authenticate user")
         .expect("should have authenticated user");
@@ -36,21 +209,260 @@ fn authenticate_user() {
     assert_eq!(created, user);
 }
 
+#[test]
+fn authenticate_user_with_backoff_and_lockout() {
+    let now = Utc::now();
+    let at = |secs: i64| memory::Clock::new(now + Duration::seconds(secs));
+    let policy = PasswordPolicy::default();
+    let login_policy = LoginPolicy::default();
+    let mut repo: Box<dyn todo::UserRepo> = Box::new(memory::UserRepo::new());
+    let mut attempts: Box<dyn todo::LoginAttemptRepo> = Box::new(memory::LoginAttemptRepo::new());
+
+    let (email, password) = ("test@example.com", "aiueo-1234");
+    usecase::CreateUser::new(&mut repo, &at(0), &policy)
+        .invoke(email, password)
+        .unwrap();
+
+    let mut elapsed = 0;
+    for (failures, backoff) in [1, 2, 4, 8].iter().enumerate() {
+        let user = usecase::AuthenticateUser::new(
+            repo.as_ref(),
+            &mut attempts,
+            &at(elapsed),
+            &login_policy,
+        )
+        .invoke("TEST@example.com", "wrong-1234")
+        .unwrap();
+        assert!(user.is_none());
+        assert_eq!(
+            failures as u32 + 1,
+            attempts.find_by_email(email).unwrap().unwrap().failures()
+        );
+
+        let err = usecase::AuthenticateUser::new(
+            repo.as_ref(),
+            &mut attempts,
+            &at(elapsed),
+            &login_policy,
+        )
+        .invoke(email, password)
+        .unwrap_err();
+        assert_eq!(
+            todo::Error::TooManyAttempts(
+                format!("too many failed login attempts; try again in {}s", backoff),
+                Duration::seconds(*backoff)
+            ),
+            err
+        );
+        elapsed += backoff;
+    }
+
+    usecase::AuthenticateUser::new(repo.as_ref(), &mut attempts, &at(elapsed), &login_policy)
+        .invoke(email, "wrong-1234")
+        .unwrap();
+    let err = usecase::AuthenticateUser::new(
+        repo.as_ref(),
+        &mut attempts,
+        &at(elapsed + 1),
+        &login_policy,
+    )
+    .invoke(email, password)
+    .unwrap_err();
+    assert_eq!(
+        todo::Error::TooManyAttempts(
+            String::from("too many failed login attempts; try again in 14m 59s"),
+            Duration::seconds(899)
+        ),
+        err
+    );
+    assert_eq!(Some(Duration::seconds(899)), err.retry_after());
+
+    elapsed += 900;
+    for lockout in [30, 60].iter() {
+        usecase::AuthenticateUser::new(repo.as_ref(), &mut attempts, &at(elapsed), &login_policy)
+            .invoke(email, "wrong-1234")
+            .unwrap();
+        let err = usecase::AuthenticateUser::new(
+            repo.as_ref(),
+            &mut attempts,
+            &at(elapsed),
+            &login_policy,
+        )
+        .invoke(email, password)
+        .unwrap_err();
+        assert_eq!(
+            todo::Error::TooManyAttempts(
+                format!("too many failed login attempts; try again in {}m", lockout),
+                Duration::minutes(*lockout)
+            ),
+            err
+        );
+        elapsed += lockout * 60;
+    }
+
+    let user =
+        usecase::AuthenticateUser::new(repo.as_ref(), &mut attempts, &at(elapsed), &login_policy)
+            .invoke(email, password)
+            .unwrap();
+    assert!(user.is_some());
+    assert_eq!(None, attempts.find_by_email(email).unwrap());
+}
+
+#[test]
+fn change_password() {
+    let clock = memory::Clock::new(Utc::now());
+    let policy = PasswordPolicy::default();
+    let login_policy = LoginPolicy::default();
+    let mut repo: Box<dyn todo::UserRepo> = Box::new(memory::UserRepo::new());
+    let mut attempts: Box<dyn todo::LoginAttemptRepo> = Box::new(memory::LoginAttemptRepo::new());
+
+    let (email, password) = ("test@example.com", "aiueo-1234");
+    let user = usecase::CreateUser::new(&mut repo, &clock, &policy)
+        .invoke(email, password)
+        .unwrap();
+
+    let err =
+        usecase::ChangePassword::new(&mut repo, &mut attempts, &clock, &policy, &login_policy)
+            .invoke(user.id(), "wrong-1234", "kakikukeko-5678")
+            .unwrap_err();
+    assert_eq!(
+        todo::Error::Unauthorized(String::from("current password is incorrect")),
+        err
+    );
+
+    let clock = memory::Clock::new(clock.now() + Duration::seconds(1));
+    let err =
+        usecase::ChangePassword::new(&mut repo, &mut attempts, &clock, &policy, &login_policy)
+            .invoke(user.id(), password, "password")
+            .unwrap_err();
+    assert_eq!("validation", err.kind());
+    assert_eq!(None, attempts.find_by_email(email).unwrap());
+
+    usecase::ChangePassword::new(&mut repo, &mut attempts, &clock, &policy, &login_policy)
+        .invoke(user.id(), password, "kakikukeko-5678")
+        .expect("should have changed password");
+
+    assert!(
+        usecase::AuthenticateUser::new(repo.as_ref(), &mut attempts, &clock, &login_policy)
+            .invoke(email, password)
+            .unwrap()
+            .is_none()
+    );
+    let clock = memory::Clock::new(clock.now() + Duration::seconds(1));
+    assert!(
+        usecase::AuthenticateUser::new(repo.as_ref(), &mut attempts, &clock, &login_policy)
+            .invoke(email, "kakikukeko-5678")
+            .unwrap()
+            .is_some()
+    );
+}
+
+#[test]
+fn change_password_with_backoff() {
+    let clock = memory::Clock::new(Utc::now());
+    let policy = PasswordPolicy::default();
+    let login_policy = LoginPolicy::default();
+    let mut repo: Box<dyn todo::UserRepo> = Box::new(memory::UserRepo::new());
+    let mut attempts: Box<dyn todo::LoginAttemptRepo> = Box::new(memory::LoginAttemptRepo::new());
+
+    let (email, password) = ("test@example.com", "aiueo-1234");
+    let user = usecase::CreateUser::new(&mut repo, &clock, &policy)
+        .invoke(email, password)
+        .unwrap();
+
+    let mut change_password = |current: &str| {
+        usecase::ChangePassword::new(&mut repo, &mut attempts, &clock, &policy, &login_policy)
+            .invoke(user.id(), current, "kakikukeko-5678")
+            .unwrap_err()
+    };
+    assert_eq!("unauthorized", change_password("wrong-1234").kind());
+    assert_eq!(
+        todo::Error::TooManyAttempts(
+            String::from("too many failed login attempts; try again in 1s"),
+            Duration::seconds(1)
+        ),
+        change_password(password)
+    );
+
+    let err = usecase::AuthenticateUser::new(repo.as_ref(), &mut attempts, &clock, &login_policy)
+        .invoke(email, password)
+        .unwrap_err();
+    assert_eq!(Some(Duration::seconds(1)), err.retry_after());
+}
+
+#[test]
+fn throttle_unknown_emails_like_known_ones() {
+    let clock = memory::Clock::new(Utc::now());
+    let login_policy = LoginPolicy::default();
+    let repo: Box<dyn todo::UserRepo> = Box::new(memory::UserRepo::new());
+    let mut attempts: Box<dyn todo::LoginAttemptRepo> = Box::new(memory::LoginAttemptRepo::new());
+
+    let user = usecase::AuthenticateUser::new(repo.as_ref(), &mut attempts, &clock, &login_policy)
+        .invoke("Nobody@Example.com", "wrong-1234")
+        .unwrap();
+    assert!(user.is_none());
+    assert_eq!(
+        1,
+        attempts
+            .find_by_email("nobody@example.com")
+            .unwrap()
+            .unwrap()
+            .failures()
+    );
+
+    let err = usecase::AuthenticateUser::new(repo.as_ref(), &mut attempts, &clock, &login_policy)
+        .invoke("nobody@example.com", "wrong-1234")
+        .unwrap_err();
+    assert_eq!(Some(Duration::seconds(1)), err.retry_after());
+}
+
+#[test]
+fn change_email() {
+    let clock = memory::Clock::new(Utc::now());
+    let policy = PasswordPolicy::default();
+    let mut repo: Box<dyn todo::UserRepo> = Box::new(memory::UserRepo::new());
+
+    let user = usecase::CreateUser::new(&mut repo, &clock, &policy)
+        .invoke("test@example.com", "aiueo-1234")
+        .unwrap();
+    usecase::CreateUser::new(&mut repo, &clock, &policy)
+        .invoke("other@example.com", "aiueo-1234")
+        .unwrap();
+
+    let err = usecase::ChangeEmail::new(&mut repo)
+        .invoke(user.id(), "other@example.com")
+        .unwrap_err();
+    assert_eq!(todo::Error::email_taken(), err);
+
+    let changed = usecase::ChangeEmail::new(&mut repo)
+        .invoke(user.id(), "new@example.com")
+        .expect("should have changed email");
+
+    assert_eq!("new@example.com", changed.email());
+    assert_eq!(
+        Some(changed),
+        repo.find_by_email("new@example.com").unwrap()
+    );
+    assert_eq!(None, repo.find_by_email("test@example.com").unwrap());
+}
+
 #[test]
 fn delete_user() {
+    let clock = memory::Clock::new(Utc::now());
+    let policy = PasswordPolicy::default();
     let mut user_repo: Box<dyn todo::UserRepo> = Box::new(memory::UserRepo::new());
     let mut task_repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
 
-    let (email, password) = ("test@example.com", "aiueo");
-    let user = usecase::CreateUser::new(&mut user_repo)
+    let (email, password) = ("test@example.com", "aiueo-1234");
+    let user = usecase::CreateUser::new(&mut user_repo, &clock, &policy)
         .invoke(email, password)
         .unwrap();
 
-    usecase::CreateTask::new(&mut task_repo)
-        .invoke(user.id(), "test task name 1")
+    usecase::CreateTask::new(&mut task_repo, &clock)
+        .invoke(user.id(), "test task name 1", None, Priority::None)
         .unwrap();
-    usecase::CreateTask::new(&mut task_repo)
-        .invoke(user.id(), "test task name 2")
+    usecase::CreateTask::new(&mut task_repo, &clock)
+        .invoke(user.id(), "test task name 2", None, Priority::None)
         .unwrap();
 
     usecase::DeleteUser::new(&mut user_repo, &mut task_repo)
@@ -60,8 +472,8 @@ fn delete_user() {
     assert_eq!(None, user_repo.find_by_email(user.email()).unwrap());
     assert_eq!(
         0,
-        usecase::GetTasks::new(&task_repo)
-            .invoke(user.id())
+        usecase::QueryTasks::new(task_repo.as_ref())
+            .invoke(user.id(), &TaskQuery::default())
             .unwrap()
             .len()
     );
@@ -69,62 +481,487 @@ fn delete_user() {
 
 #[test]
 fn get_tasks() {
+    let clock = memory::Clock::new(Utc::now());
     let mut repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
 
     let user_id = "test user id";
-    let created = usecase::CreateTask::new(&mut repo)
-        .invoke(&user_id, "test task name")
+    let created = usecase::CreateTask::new(&mut repo, &clock)
+        .invoke(user_id, "test task name", None, Priority::None)
         .unwrap();
 
-    let tasks = usecase::GetTasks::new(&repo)
-        .invoke(&user_id)
+    let tasks = usecase::QueryTasks::new(repo.as_ref())
+        .invoke(user_id, &TaskQuery::default())
         .expect("should have succeeded to get tasks");
 
     assert_eq!(1, tasks.len());
-    assert_eq!(created, *tasks.get(0).unwrap());
+    assert_eq!(created, *tasks.first().unwrap());
+}
+
+#[test]
+fn get_overdue_tasks() {
+    let clock = memory::Clock::new(Utc::now());
+    let mut repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
+
+    let (user_id, now) = ("test user id", clock.now());
+    let overdue = usecase::CreateTask::new(&mut repo, &clock)
+        .invoke(
+            user_id,
+            "test task name 1",
+            Some(now - Duration::days(1)),
+            Priority::None,
+        )
+        .unwrap();
+    usecase::CreateTask::new(&mut repo, &clock)
+        .invoke(
+            user_id,
+            "test task name 2",
+            Some(now + Duration::days(1)),
+            Priority::None,
+        )
+        .unwrap();
+    usecase::CreateTask::new(&mut repo, &clock)
+        .invoke(user_id, "test task name 3", None, Priority::None)
+        .unwrap();
+
+    let mut query = TaskQuery::default();
+    usecase::DueFilter::Overdue.apply(&mut query, now).unwrap();
+    let tasks = usecase::QueryTasks::new(repo.as_ref())
+        .invoke(user_id, &query)
+        .expect("should have succeeded to get tasks");
+
+    assert_eq!(vec![overdue], tasks);
+}
+
+#[test]
+fn get_tasks_due_within() {
+    let clock = memory::Clock::new(Utc::now());
+    let mut repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
+
+    let (user_id, now) = ("test user id", clock.now());
+    let upcoming = usecase::CreateTask::new(&mut repo, &clock)
+        .invoke(
+            user_id,
+            "test task name 1",
+            Some(now + Duration::hours(1)),
+            Priority::None,
+        )
+        .unwrap();
+    usecase::CreateTask::new(&mut repo, &clock)
+        .invoke(
+            user_id,
+            "test task name 2",
+            Some(now + Duration::days(3)),
+            Priority::None,
+        )
+        .unwrap();
+    usecase::CreateTask::new(&mut repo, &clock)
+        .invoke(
+            user_id,
+            "test task name 3",
+            Some(now - Duration::hours(1)),
+            Priority::None,
+        )
+        .unwrap();
+
+    let mut query = TaskQuery::default();
+    usecase::DueFilter::Within(Duration::days(1))
+        .apply(&mut query, now)
+        .unwrap();
+    let tasks = usecase::QueryTasks::new(repo.as_ref())
+        .invoke(user_id, &query)
+        .expect("should have succeeded to get tasks");
+
+    assert_eq!(vec![upcoming], tasks);
+}
+
+#[test]
+fn get_tasks_at_due_boundaries() {
+    let clock = memory::Clock::new(Utc::now());
+    let mut repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
+
+    let (user_id, now) = ("test user id", clock.now());
+    let due_now = usecase::CreateTask::new(&mut repo, &clock)
+        .invoke(user_id, "test task name 1", Some(now), Priority::None)
+        .unwrap();
+    usecase::CreateTask::new(&mut repo, &clock)
+        .invoke(
+            user_id,
+            "test task name 2",
+            Some(now + Duration::days(1)),
+            Priority::None,
+        )
+        .unwrap();
+
+    let get = |due: usecase::DueFilter| {
+        let mut query = TaskQuery::default();
+        due.apply(&mut query, now).unwrap();
+        usecase::QueryTasks::new(repo.as_ref())
+            .invoke(user_id, &query)
+            .expect("should have succeeded to get tasks")
+    };
+
+    assert!(get(usecase::DueFilter::Overdue).is_empty());
+    assert_eq!(
+        vec![due_now],
+        get(usecase::DueFilter::Within(Duration::days(1)))
+    );
+}
+
+#[test]
+fn reject_due_window_out_of_range() {
+    let mut query = TaskQuery::default();
+    let within = usecase::DueFilter::Within(Duration::weeks(9_999_999_999));
+
+    match within.apply(&mut query, Utc::now()) {
+        Err(todo::Error::Validation(_)) => {}
+        other => panic!("should have rejected due window, got {:?}", other),
+    }
+}
+
+#[test]
+fn get_tasks_by_priority() {
+    let clock = memory::Clock::new(Utc::now());
+    let mut repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
+
+    let user_id = "test user id";
+    let mut created = Vec::new();
+    for priority in &[
+        Priority::Low,
+        Priority::Urgent,
+        Priority::None,
+        Priority::High,
+    ] {
+        created.push(
+            usecase::CreateTask::new(&mut repo, &clock)
+                .invoke(user_id, "test task name", None, *priority)
+                .unwrap(),
+        );
+    }
+
+    let tasks = usecase::QueryTasks::new(repo.as_ref())
+        .invoke(
+            user_id,
+            &TaskQuery {
+                sort: TaskSort::new(TaskSortKey::Priority),
+                ..Default::default()
+            },
+        )
+        .expect("should have succeeded to get tasks");
+
+    let priorities: Vec<Priority> = tasks.iter().map(|task| task.priority()).collect();
+    assert_eq!(
+        vec![
+            Priority::Urgent,
+            Priority::High,
+            Priority::Low,
+            Priority::None
+        ],
+        priorities
+    );
+}
+
+#[test]
+fn get_tagged_tasks() {
+    let clock = memory::Clock::new(Utc::now());
+    let mut repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
+
+    let user_id = "test user id";
+    let mut created = Vec::new();
+    for tags in &[vec!["work", "home"], vec!["work"], vec!["home"], vec![]] {
+        let task = usecase::CreateTask::new(&mut repo, &clock)
+            .invoke(user_id, "test task name", None, Priority::None)
+            .unwrap();
+        let task = usecase::UpdateTask::new(&mut repo, &clock)
+            .invoke(
+                task.id(),
+                user_id,
+                &usecase::TaskUpdate {
+                    add_tags: tags.iter().map(|tag| tag.to_string()).collect(),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        created.push(task);
+    }
+
+    let get = |filter: TagFilter| {
+        let mut tasks = usecase::QueryTasks::new(repo.as_ref())
+            .invoke(
+                user_id,
+                &TaskQuery {
+                    tags: Some(filter),
+                    ..Default::default()
+                },
+            )
+            .expect("should have succeeded to get tasks");
+        tasks.sort_by_key(|task| created.iter().position(|created| created == task));
+        tasks
+    };
+
+    assert_eq!(
+        vec![created[0].clone()],
+        get(TagFilter::all(&["Work", "home"]).unwrap())
+    );
+    assert_eq!(
+        created[..3].to_vec(),
+        get(TagFilter::any(&["work", "home"]).unwrap())
+    );
+}
+
+#[test]
+fn query_tasks() {
+    let clock = memory::Clock::new(Utc::now());
+    let mut repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
+
+    let (user_id, now) = ("test user id", clock.now());
+    for (name, days) in &[("write report", 3), ("Read book", 1), ("write code", 2)] {
+        usecase::CreateTask::new(&mut repo, &clock)
+            .invoke(
+                user_id,
+                name,
+                Some(now + Duration::days(*days)),
+                Priority::None,
+            )
+            .unwrap();
+    }
+    let completed = usecase::CreateTask::new(&mut repo, &clock)
+        .invoke(user_id, "write diary", None, Priority::None)
+        .unwrap();
+    usecase::CompleteTask::new(&mut repo, &clock)
+        .invoke(completed.id(), user_id)
+        .unwrap();
+
+    let query = |query: TaskQuery| -> Vec<String> {
+        usecase::QueryTasks::new(repo.as_ref())
+            .invoke(user_id, &query)
+            .expect("should have succeeded to query tasks")
+            .iter()
+            .map(|task| task.name().clone())
+            .collect()
+    };
+
+    assert_eq!(
+        vec!["write code", "write report"],
+        query(TaskQuery {
+            completed: Some(false),
+            name_contains: Some("WRITE".to_string()),
+            sort: TaskSort::new(TaskSortKey::Due),
+            ..Default::default()
+        })
+    );
+    assert_eq!(
+        vec!["write diary", "write code"],
+        query(TaskQuery {
+            sort: TaskSort {
+                key: TaskSortKey::Name,
+                direction: SortDirection::Desc,
+            },
+            limit: Some(2),
+            offset: 1,
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn get_task() {
+    let clock = memory::Clock::new(Utc::now());
+    let mut repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
+    let user_id = "test user id";
+    let created = usecase::CreateTask::new(&mut repo, &clock)
+        .invoke(user_id, "test task name", None, Priority::None)
+        .unwrap();
+
+    let got = usecase::GetTask::new(repo.as_ref())
+        .invoke(created.id(), user_id)
+        .expect("should have succeeded to get task");
+    assert_eq!(created, got);
+
+    let err = usecase::GetTask::new(repo.as_ref())
+        .invoke(created.id(), "other user id")
+        .unwrap_err();
+    assert_eq!(todo::Error::NotFound(String::from("no such task")), err);
 }
 
 #[test]
 fn create_task() {
+    let clock = memory::Clock::new(Utc::now());
     let mut repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
 
     let (user_id, name) = ("test user id", "test task name");
-    let task = usecase::CreateTask::new(&mut repo)
-        .invoke(user_id, name)
+    let task = usecase::CreateTask::new(&mut repo, &clock)
+        .invoke(user_id, name, None, Priority::None)
         .expect("should have succeeded to create task");
 
     assert_eq!(user_id, task.user_id());
     assert_eq!(name, task.name());
+    assert_eq!(clock.now(), task.created_at());
+    assert_eq!(clock.now(), task.updated_at());
+    assert_eq!(None, task.completed_at());
 }
 
 #[test]
 fn complete_task() {
+    let clock = memory::Clock::new(Utc::now());
     let mut repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
 
     let user_id = "test user id";
-    let created = usecase::CreateTask::new(&mut repo)
-        .invoke(&user_id, "test task name")
+    let created = usecase::CreateTask::new(&mut repo, &clock)
+        .invoke(user_id, "test task name", None, Priority::None)
         .expect("should have succeeded to create task");
 
-    let task = usecase::CompleteTask::new(&mut repo)
-        .invoke(created.id(), &user_id)
+    let later = memory::Clock::new(clock.now() + Duration::hours(1));
+    let task = usecase::CompleteTask::new(&mut repo, &later)
+        .invoke(created.id(), user_id)
         .expect("should have succeeded to complete task");
 
     assert!(task.is_completed());
+    assert_eq!(clock.now(), task.created_at());
+    assert_eq!(later.now(), task.updated_at());
+    assert_eq!(Some(later.now()), task.completed_at());
+}
+
+#[test]
+fn update_task() {
+    let clock = memory::Clock::new(Utc::now());
+    let mut repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
+
+    let user_id = "test user id";
+    let created = usecase::CreateTask::new(&mut repo, &clock)
+        .invoke(user_id, "test task name", None, Priority::None)
+        .unwrap();
+
+    let due = Utc::now() + Duration::days(1);
+    let task = usecase::UpdateTask::new(&mut repo, &clock)
+        .invoke(
+            created.id(),
+            user_id,
+            &usecase::TaskUpdate {
+                due: Some(Some(due)),
+                priority: Some(Priority::High),
+                add_tags: vec!["Home Work".to_string(), "urgent".to_string()],
+                remove_tags: vec!["urgent".to_string()],
+                ..Default::default()
+            },
+        )
+        .expect("should have succeeded to update task");
+
+    assert_eq!(Some(due), task.due());
+    assert_eq!(Priority::High, task.priority());
+    assert_eq!(
+        vec!["home-work"],
+        task.tags()
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<&str>>()
+    );
+}
+
+#[test]
+fn rename_and_reopen_task() {
+    let clock = memory::Clock::new(Utc::now());
+    let mut repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
+
+    let user_id = "test user id";
+    let created = usecase::CreateTask::new(&mut repo, &clock)
+        .invoke(user_id, "test tsak name", None, Priority::None)
+        .unwrap();
+    usecase::CompleteTask::new(&mut repo, &clock)
+        .invoke(created.id(), user_id)
+        .unwrap();
+
+    let task = usecase::UpdateTask::new(&mut repo, &clock)
+        .invoke(
+            created.id(),
+            user_id,
+            &usecase::TaskUpdate {
+                name: Some("test task name".to_string()),
+                completed: Some(false),
+                ..Default::default()
+            },
+        )
+        .expect("should have succeeded to update task");
+
+    assert_eq!("test task name", task.name());
+    assert!(!task.is_completed());
+
+    usecase::UpdateTask::new(&mut repo, &clock)
+        .invoke(
+            created.id(),
+            user_id,
+            &usecase::TaskUpdate {
+                name: Some("".to_string()),
+                ..Default::default()
+            },
+        )
+        .expect_err("should have failed to rename task to empty name");
+}
+
+#[test]
+fn move_task() {
+    let clock = memory::Clock::new(Utc::now());
+    let mut repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
+
+    let user_id = "test user id";
+    let mut ids = Vec::new();
+    for name in &["test task name 1", "test task name 2", "test task name 3"] {
+        let task = usecase::CreateTask::new(&mut repo, &clock)
+            .invoke(user_id, name, None, Priority::None)
+            .unwrap();
+        ids.push(task.id().clone());
+    }
+
+    let names = |repo: &dyn todo::TaskRepo| -> Vec<String> {
+        usecase::QueryTasks::new(repo)
+            .invoke(user_id, &TaskQuery::default())
+            .unwrap()
+            .iter()
+            .map(|task| task.name().clone())
+            .collect()
+    };
+
+    assert_eq!(
+        vec!["test task name 1", "test task name 2", "test task name 3"],
+        names(repo.as_ref())
+    );
+
+    usecase::MoveTask::new(&mut repo, &clock)
+        .invoke(&ids[2], user_id, &usecase::MoveTo::Top)
+        .expect("should have succeeded to move task");
+    assert_eq!(
+        vec!["test task name 3", "test task name 1", "test task name 2"],
+        names(repo.as_ref())
+    );
+
+    usecase::MoveTask::new(&mut repo, &clock)
+        .invoke(&ids[1], user_id, &usecase::MoveTo::Before(ids[0].clone()))
+        .expect("should have succeeded to move task");
+    assert_eq!(
+        vec!["test task name 3", "test task name 2", "test task name 1"],
+        names(repo.as_ref())
+    );
 }
 
 #[test]
 fn delete_task() {
+    let clock = memory::Clock::new(Utc::now());
     let mut repo: Box<dyn todo::TaskRepo> = Box::new(memory::TaskRepo::new());
     let user_id = "test user id";
-    let created = usecase::CreateTask::new(&mut repo)
-        .invoke(&user_id, "test task name")
+    let created = usecase::CreateTask::new(&mut repo, &clock)
+        .invoke(user_id, "test task name", None, Priority::None)
         .unwrap();
 
     usecase::DeleteTask::new(&mut repo)
-        .invoke(&created.id(), &user_id)
+        .invoke(created.id(), user_id)
         .expect("should have succeeded to delete task");
 
-    let got = usecase::GetTasks::new(&repo).invoke(&user_id).unwrap();
+    let got = usecase::QueryTasks::new(repo.as_ref())
+        .invoke(user_id, &TaskQuery::default())
+        .unwrap();
     assert_eq!(0, got.len());
+
+    let err = usecase::DeleteTask::new(&mut repo)
+        .invoke(created.id(), user_id)
+        .unwrap_err();
+    assert_eq!(todo::Error::NotFound(String::from("no such task")), err);
 }